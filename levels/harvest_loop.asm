@@ -0,0 +1,10 @@
+; harvests big_iron until it runs dry, then parks the unit.
+LABEL top
+JUMPIF MINE_EMPTY @big_iron done
+MOVED 5 10
+HARVEST @big_iron iron
+MOVED 0 0
+DEPOSIT @hive
+JUMP top
+LABEL done
+HALT
@@ -0,0 +1,60 @@
+-- lua version of the rust Autopilot (src/autopilot.rs).
+-- spawns a unit early on and points every programmable unit at the first
+-- mine that still has something in it.
+
+-- unit id -> mine id it was programmed for.
+local programmed_units = {}
+local current_target = nil
+
+function on_tick(tick)
+    if tick == 2 then
+        game.spawn_unit()
+    end
+
+    local hive = game.hive()
+
+    -- if the current target runs out, forget the units working it so they get a new one.
+    if current_target ~= nil then
+        local amount = game.mineable_count(current_target)
+        if amount == nil or amount == 0 then
+            for unit, target in pairs(programmed_units) do
+                if target == current_target then
+                    programmed_units[unit] = nil
+                end
+            end
+            current_target = nil
+        end
+    end
+
+    if current_target == nil then
+        for _, node in ipairs(game.mineable_nodes()) do
+            local amount = game.mineable_count(node)
+            if node ~= hive and amount ~= nil and amount > 0 then
+                current_target = node
+                break
+            end
+        end
+    end
+
+    if current_target ~= nil and hive ~= nil then
+        local x, y = game.position(current_target)
+        local hive_x, hive_y = game.position(hive)
+        -- harvest whatever the target still has, iron first.
+        local inventory = game.inventory(current_target)
+        local resource = "iron"
+        if inventory.iron == 0 and inventory.copper > 0 then
+            resource = "copper"
+        end
+        for _, unit in ipairs(game.programmable_units()) do
+            if programmed_units[unit] == nil then
+                game.load_program(unit, {
+                    {"move_d", x, y},
+                    {"harvest", current_target, resource},
+                    {"move_d", hive_x, hive_y},
+                    {"deposit", hive},
+                })
+                programmed_units[unit] = current_target
+            end
+        end
+    end
+end
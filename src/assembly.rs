@@ -0,0 +1,361 @@
+//! Plain text form of unit programs.
+//!
+//! One command per line, blank lines and anything after `;` are ignored:
+//!
+//! ```text
+//! ; keep the hive stocked from big_iron
+//! LABEL top
+//! MOVED 5 10
+//! HARVEST @big_iron iron
+//! JUMPIF MINE_EMPTY @big_iron done
+//! MOVED 0 0
+//! DEPOSIT @hive
+//! JUMP top
+//! LABEL done
+//! HALT
+//! ```
+//!
+//! | command                  | meaning                                  |
+//! |--------------------------|------------------------------------------|
+//! | `MOVEP x y`              | `Command::MoveP`                         |
+//! | `MOVED x y`              | `Command::MoveD`                         |
+//! | `HARVEST @node resource` | `Command::Harvest`                       |
+//! | `DEPOSIT @node`          | `Command::Deposit`                       |
+//! | `LABEL name`             | `Command::Label`                         |
+//! | `JUMP name`              | `Command::Jump`                          |
+//! | `JUMPIF condition name`  | `Command::JumpIf`                        |
+//! | `WAIT ticks`             | `Command::Wait`                          |
+//! | `HALT`                   | `Command::Halt`                          |
+//!
+//! Conditions are `CARGO_FULL`, `CARGO_EMPTY`, `ENERGY_LOW` and `MINE_EMPTY @node`.
+//! `@node` is looked up in the name table handed to assemble, `@12` refers to
+//! entity 12 directly. Resources are named as in level files (`iron`, `copper`).
+//! Keywords and resources are case insensitive.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entity_manager::Entity;
+use crate::game_state::{Command, Condition, GameState, Memory, Position};
+use crate::resource::Resource;
+
+/// a problem with one line of a program, lines count from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssemblyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn parse_number(word: Option<&str>, what: &str) -> Result<u32, String> {
+    let word = word.ok_or(format!("missing {}", what))?;
+    word.parse()
+        .map_err(|_| format!("{} must be a whole number, got \"{}\"", what, word))
+}
+
+fn parse_target(word: Option<&str>, names: &HashMap<String, Entity>) -> Result<Entity, String> {
+    let word = word.ok_or("missing target".to_string())?;
+    if !word.starts_with('@') {
+        return Err(format!("target must start with @, got \"{}\"", word));
+    }
+    let name = &word[1..];
+    if let Some(e) = names.get(name) {
+        return Ok(*e);
+    }
+    name.parse()
+        .map(Entity)
+        .map_err(|_| format!("unknown target \"{}\"", word))
+}
+
+fn parse_resource(word: Option<&str>) -> Result<Resource, String> {
+    let word = word.ok_or("missing resource".to_string())?;
+    Resource::from_name(word).ok_or(format!("unknown resource \"{}\"", word))
+}
+
+fn parse_label(word: Option<&str>) -> Result<String, String> {
+    word.map(|w| w.to_string())
+        .ok_or("missing label".to_string())
+}
+
+fn parse_line(line: &str, names: &HashMap<String, Entity>) -> Result<Option<Command>, String> {
+    let code = line.split(';').next().unwrap();
+    let mut words = code.split_whitespace();
+    let keyword = match words.next() {
+        Some(w) => w.to_uppercase(),
+        None => return Ok(None),
+    };
+
+    let command = match keyword.as_str() {
+        "MOVEP" => Command::MoveP(Position::new(
+            parse_number(words.next(), "x")?,
+            parse_number(words.next(), "y")?,
+        )),
+        "MOVED" => Command::MoveD(Position::new(
+            parse_number(words.next(), "x")?,
+            parse_number(words.next(), "y")?,
+        )),
+        "HARVEST" => Command::Harvest(
+            parse_target(words.next(), names)?,
+            parse_resource(words.next())?,
+        ),
+        "DEPOSIT" => Command::Deposit(parse_target(words.next(), names)?),
+        "LABEL" => Command::Label(parse_label(words.next())?),
+        "JUMP" => Command::Jump(parse_label(words.next())?),
+        "JUMPIF" => {
+            let condition = match words.next().map(|w| w.to_uppercase()) {
+                Some(ref c) if c == "CARGO_FULL" => Condition::CargoFull,
+                Some(ref c) if c == "CARGO_EMPTY" => Condition::CargoEmpty,
+                Some(ref c) if c == "ENERGY_LOW" => Condition::EnergyLow,
+                Some(ref c) if c == "MINE_EMPTY" => {
+                    Condition::MineEmpty(parse_target(words.next(), names)?)
+                }
+                Some(c) => return Err(format!("unknown condition \"{}\"", c)),
+                None => return Err("missing condition".to_string()),
+            };
+            Command::JumpIf(condition, parse_label(words.next())?)
+        }
+        "WAIT" => Command::Wait(parse_number(words.next(), "tick count")?),
+        "HALT" => Command::Halt,
+        _ => return Err(format!("unknown command \"{}\"", keyword)),
+    };
+
+    if let Some(extra) = words.next() {
+        return Err(format!("unexpected \"{}\" after {}", extra, keyword));
+    }
+    Ok(Some(command))
+}
+
+/// parses program text into commands, every JUMP/JUMPIF must name a LABEL of the same program.
+pub fn assemble(
+    source: &str,
+    names: &HashMap<String, Entity>,
+) -> Result<Vec<Command>, AssemblyError> {
+    let mut program = Vec::new();
+    // line of every command, for reporting unknown labels.
+    let mut lines = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let parsed = parse_line(line, names).map_err(|message| AssemblyError {
+            line: i + 1,
+            message: message,
+        })?;
+        if let Some(command) = parsed {
+            program.push(command);
+            lines.push(i + 1);
+        }
+    }
+
+    for (command, line) in program.iter().zip(lines.iter()) {
+        let label = match command {
+            Command::Jump(l) | Command::JumpIf(_, l) => l,
+            _ => continue,
+        };
+        let found = program.iter().any(|c| match c {
+            Command::Label(other) => other == label,
+            _ => false,
+        });
+        if !found {
+            return Err(AssemblyError {
+                line: *line,
+                message: format!("unknown label \"{}\"", label),
+            });
+        }
+    }
+
+    return Ok(program);
+}
+
+fn target_text(entity: &Entity, names: &HashMap<String, Entity>) -> String {
+    // smallest name wins so the output doesn't depend on HashMap order.
+    let name = names
+        .iter()
+        .filter(|(_, e)| *e == entity)
+        .map(|(n, _)| n)
+        .min();
+    match name {
+        Some(n) => format!("@{}", n),
+        None => format!("@{}", entity.0),
+    }
+}
+
+/// prints commands in the form assemble reads back.
+pub fn disassemble_commands(commands: &[Command], names: &HashMap<String, Entity>) -> String {
+    let mut res = String::new();
+    for command in commands {
+        let line = match command {
+            Command::MoveP(p) => format!("MOVEP {} {}", p.get_x(), p.get_y()),
+            Command::MoveD(p) => format!("MOVED {} {}", p.get_x(), p.get_y()),
+            Command::Harvest(e, r) => format!("HARVEST {} {}", target_text(e, names), r),
+            Command::Deposit(e) => format!("DEPOSIT {}", target_text(e, names)),
+            Command::Label(l) => format!("LABEL {}", l),
+            Command::Jump(l) => format!("JUMP {}", l),
+            Command::JumpIf(condition, l) => {
+                let condition = match condition {
+                    Condition::CargoFull => "CARGO_FULL".to_string(),
+                    Condition::CargoEmpty => "CARGO_EMPTY".to_string(),
+                    Condition::EnergyLow => "ENERGY_LOW".to_string(),
+                    Condition::MineEmpty(e) => format!("MINE_EMPTY {}", target_text(e, names)),
+                };
+                format!("JUMPIF {} {}", condition, l)
+            }
+            Command::Wait(ticks) => format!("WAIT {}", ticks),
+            Command::Halt => "HALT".to_string(),
+        };
+        res += &line;
+        res += "\n";
+    }
+    return res;
+}
+
+/// prints the program held by a unit's memory.
+/// units running a lua script only hold the commands not yet finished.
+pub fn disassemble(memory: &Memory, names: &HashMap<String, Entity>) -> String {
+    let mut res = String::new();
+    if memory.script.is_some() {
+        res += "; fed by a lua script, remaining commands only\n";
+    }
+    res += &disassemble_commands(&memory.commands, names);
+    return res;
+}
+
+/// prints the program of every programmable unit under a `; unit N` comment,
+/// lowest id first. the hive is written as `@hive`, other targets by id.
+pub fn disassemble_units(game_state: &GameState) -> String {
+    let mut names = HashMap::new();
+    if let Some(hive) = game_state.hive_entity {
+        names.insert("hive".to_string(), hive);
+    }
+    let mut units = game_state.get_programable_units();
+    units.sort_by_key(|e| e.0);
+
+    let mut res = String::new();
+    for unit in units {
+        res += &format!("; unit {}\n", unit.0);
+        res += &disassemble(game_state.memory.get(unit).unwrap(), &names);
+    }
+    return res;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: &str = "\
+; keep the hive stocked
+LABEL top
+MOVED 5 10
+harvest @big_iron IRON   ; keywords are case insensitive
+JUMPIF MINE_EMPTY @big_iron done
+
+MOVEP 0 0
+DEPOSIT @hive
+JUMPIF CARGO_FULL top
+WAIT 3
+JUMP top
+LABEL done
+HALT
+";
+
+    fn names() -> HashMap<String, Entity> {
+        let mut names = HashMap::new();
+        names.insert("hive".to_string(), Entity(1));
+        names.insert("big_iron".to_string(), Entity(3));
+        names
+    }
+
+    fn expect_error(source: &str, line: usize) {
+        match assemble(source, &names()) {
+            Err(e) => assert_eq!(e.line, line, "{}", e),
+            Ok(p) => panic!("program should not assemble: {:?}", p),
+        }
+    }
+
+    #[test]
+    fn assemble_program() {
+        let program = assemble(PROGRAM, &names()).unwrap();
+        assert_eq!(
+            program,
+            vec![
+                Command::Label("top".to_string()),
+                Command::MoveD(Position::new(5, 10)),
+                Command::Harvest(Entity(3), Resource::Iron),
+                Command::JumpIf(Condition::MineEmpty(Entity(3)), "done".to_string()),
+                Command::MoveP(Position::new(0, 0)),
+                Command::Deposit(Entity(1)),
+                Command::JumpIf(Condition::CargoFull, "top".to_string()),
+                Command::Wait(3),
+                Command::Jump("top".to_string()),
+                Command::Label("done".to_string()),
+                Command::Halt,
+            ]
+        );
+    }
+
+    #[test]
+    fn assemble_raw_entity_ids() {
+        let program = assemble("HARVEST @7 copper\nDEPOSIT @1", &HashMap::new()).unwrap();
+        assert_eq!(
+            program,
+            vec![
+                Command::Harvest(Entity(7), Resource::Copper),
+                Command::Deposit(Entity(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn disassemble_round_trip() {
+        let program = assemble(PROGRAM, &names()).unwrap();
+        let text = disassemble_commands(&program, &names());
+        assert!(text.contains("HARVEST @big_iron iron\n"));
+        assert_eq!(assemble(&text, &names()).unwrap(), program);
+
+        // without names targets fall back to entity ids.
+        let text = disassemble_commands(&program, &HashMap::new());
+        assert!(text.contains("DEPOSIT @1\n"));
+        assert_eq!(assemble(&text, &HashMap::new()).unwrap(), program);
+    }
+
+    #[test]
+    fn disassemble_memory() {
+        let memory = Memory {
+            commands: vec![Command::MoveD(Position::new(1, 2)), Command::Halt],
+            ..Memory::default()
+        };
+        assert_eq!(disassemble(&memory, &names()), "MOVED 1 2\nHALT\n");
+    }
+
+    #[test]
+    fn disassemble_game_units() {
+        let mut game_state = GameState::new();
+        game_state.create_hive(0, 0);
+        let hive = game_state.hive_entity.unwrap();
+        let unit = crate::game_state::spawn_unit(&mut game_state, Position::new(0, 1));
+        game_state.memory.get_mut(&unit).unwrap().commands =
+            vec![Command::Deposit(hive), Command::Halt];
+
+        assert_eq!(
+            disassemble_units(&game_state),
+            format!("; unit {}\nDEPOSIT @hive\nHALT\n", unit.0)
+        );
+    }
+
+    #[test]
+    fn assemble_reports_line_numbers() {
+        expect_error("MOVED 1 1\n\nFLY 2 2", 3);
+        expect_error("MOVED 1", 1);
+        expect_error("MOVED 1 -2", 1);
+        expect_error("WAIT 1\nHARVEST big_iron iron", 2);
+        expect_error("HARVEST @nowhere iron", 1);
+        expect_error("HARVEST @big_iron", 1);
+        expect_error("HARVEST @big_iron gold", 1);
+        expect_error("JUMPIF SOMETIMES top\nLABEL top", 1);
+        expect_error("HALT now", 1);
+        expect_error("LABEL a\n; comment\nJUMP b", 3);
+    }
+}
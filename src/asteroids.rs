@@ -8,41 +8,283 @@ use sdl2;
 #[cfg(feature = "gui")]
 use sdl2::pixels::Color;
 #[cfg(feature = "gui")]
-use sdl2::rect::Rect;
+use sdl2::event::Event;
+#[cfg(feature = "gui")]
+use sdl2::keyboard::{Keycode, Scancode};
+#[cfg(feature = "gui")]
+use sdl2::rect::{Point, Rect};
+#[cfg(feature = "gui")]
+use sdl2::render::{TextureCreator, WindowCanvas};
+#[cfg(feature = "gui")]
+use sdl2::ttf::Font;
+#[cfg(feature = "gui")]
+use sdl2::video::WindowContext;
 #[cfg(feature = "gui")]
 use sdl2::render::Canvas;
 #[cfg(feature = "gui")]
 use sdl2::video::Window;
 
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
 use crate::collision;
-use rand::Rng;
+use crate::vec2::Vec2;
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// tunable parameters of a game, difficulty presets are RON files of this
+/// (see load_config), left out fields keep their defaults:
+///
+/// ```text
+/// (min_asteroids: 8, max_asteroids: 12, shoot_cooldown: 30)
+/// ```
+// a misspelt field would otherwise quietly keep its default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub world_width: f64,
+    pub world_height: f64,
+    /// asteroids at the start, picked between min and max (both included).
+    /// max_asteroids is at most MAX_ASTEROIDS.
+    pub min_asteroids: u32,
+    pub max_asteroids: u32,
+    /// radius and speed range of a large asteroid, smaller ones scale them.
+    pub asteroid_radius: f64,
+    pub asteroid_min_speed: f64,
+    pub asteroid_max_speed: f64,
+    /// update time a bullet exists for.
+    pub bullet_life_time: f64,
+    /// how much faster than the player a bullet flies.
+    pub bullet_speed: f64,
+    pub bullet_radius: f64,
+    /// updates between two shots.
+    pub shoot_cooldown: i16,
+    /// speed the player gains per update time while the thrusters are on.
+    pub thrust_acceleration: f64,
+    /// share of the player's velocity lost per update time, 0 keeps it drifting forever.
+    pub drag: f64,
+    /// fastest the player can fly.
+    pub max_speed: f64,
+    /// radius of the player's hitbox.
+    pub player_radius: f64,
+}
+
+/// most asteroids a game may start with.
+pub const MAX_ASTEROIDS: u32 = 100;
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            world_width: 100.0,
+            world_height: 100.0,
+            min_asteroids: 5,
+            max_asteroids: 9,
+            asteroid_radius: 8.0,
+            asteroid_min_speed: 1.0,
+            asteroid_max_speed: 2.0,
+            bullet_life_time: 20.0,
+            bullet_speed: 2.0,
+            bullet_radius: 2.0,
+            shoot_cooldown: 20,
+            thrust_acceleration: 1.0,
+            drag: 0.5,
+            max_speed: 2.0,
+            player_radius: 2.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// file isn't valid RON.
+    Parse(String),
+    /// the values don't make a playable game.
+    Invalid(String),
+}
 
-#[derive(Debug, Clone)]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read game config: {}", e),
+            ConfigError::Parse(e) => write!(f, "failed to parse game config: {}", e),
+            ConfigError::Invalid(e) => write!(f, "invalid game config: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl GameConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("world_width", self.world_width),
+            ("world_height", self.world_height),
+            ("asteroid_radius", self.asteroid_radius),
+            ("bullet_life_time", self.bullet_life_time),
+            ("bullet_speed", self.bullet_speed),
+            ("bullet_radius", self.bullet_radius),
+            ("player_radius", self.player_radius),
+            ("max_speed", self.max_speed),
+        ];
+        for (name, value) in positive.iter() {
+            if !(value.is_finite() && *value > 0.0) {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must be a finite number above 0",
+                    name
+                )));
+            }
+        }
+        if self.max_asteroids > MAX_ASTEROIDS {
+            return Err(ConfigError::Invalid(format!(
+                "max_asteroids can't be above {}",
+                MAX_ASTEROIDS
+            )));
+        }
+        if self.min_asteroids > self.max_asteroids {
+            return Err(ConfigError::Invalid(
+                "min_asteroids is above max_asteroids".to_string(),
+            ));
+        }
+        if !(self.asteroid_min_speed >= 0.0
+            && self.asteroid_min_speed < self.asteroid_max_speed
+            && self.asteroid_max_speed.is_finite())
+        {
+            return Err(ConfigError::Invalid(
+                "asteroid speeds must satisfy 0 <= asteroid_min_speed < asteroid_max_speed"
+                    .to_string(),
+            ));
+        }
+        if !(self.thrust_acceleration >= 0.0
+            && self.thrust_acceleration.is_finite()
+            && self.drag >= 0.0
+            && self.drag.is_finite())
+        {
+            return Err(ConfigError::Invalid(
+                "thrust_acceleration and drag must be finite and not negative".to_string(),
+            ));
+        }
+        if self.shoot_cooldown < 0 {
+            return Err(ConfigError::Invalid(
+                "shoot_cooldown can't be negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// parses and validates a config written in RON.
+pub fn config_from_str(contents: &str) -> Result<GameConfig, ConfigError> {
+    let config: GameConfig =
+        ron::de::from_str(contents).map_err(|e| ConfigError::Parse(e.to_string()))?;
+    config.validate()?;
+    Ok(config)
+}
+
+pub fn load_config(path: &Path) -> Result<GameConfig, ConfigError> {
+    config_from_str(&fs::read_to_string(path)?)
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct MoveAblePos {
-    pub pos_x: f64,
-    pub pos_y: f64,
-    velocity: f64,
-    /// can only be values of 0 -> 2PI.
+    pub pos: Vec2,
+    /// world units moved per update time.
+    velocity: Vec2,
+    /// where it's facing, can only be values of 0 -> 2PI.
+    /// the player can face away from where it's flying.
     direction: f64,
 }
 
-#[derive(Clone, Debug)]
+/// every game starts with large asteroids, a hit one breaks into a few of
+/// the next size down and small ones are destroyed for good.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeClass {
+    Large,
+    Medium,
+    Small,
+}
+
+impl SizeClass {
+    /// what a hit asteroid breaks into.
+    pub fn smaller(self) -> Option<SizeClass> {
+        match self {
+            SizeClass::Large => Some(SizeClass::Medium),
+            SizeClass::Medium => Some(SizeClass::Small),
+            SizeClass::Small => None,
+        }
+    }
+
+    pub fn radius(self, config: &GameConfig) -> f64 {
+        match self {
+            SizeClass::Large => config.asteroid_radius,
+            SizeClass::Medium => config.asteroid_radius * 0.5,
+            SizeClass::Small => config.asteroid_radius * 0.25,
+        }
+    }
+
+    /// smaller asteroids fly faster.
+    pub fn speed_range(self, config: &GameConfig) -> (f64, f64) {
+        let scale = match self {
+            SizeClass::Large => 1.0,
+            SizeClass::Medium => 1.5,
+            SizeClass::Small => 2.0,
+        };
+        (config.asteroid_min_speed * scale, config.asteroid_max_speed * scale)
+    }
+
+    /// how many pieces it breaks into when hit.
+    pub fn split_count(self) -> u32 {
+        match self {
+            SizeClass::Large => 2,
+            SizeClass::Medium => 2,
+            SizeClass::Small => 0,
+        }
+    }
+
+    /// most a piece veers off the broken asteroid's heading, in radians.
+    pub fn spread(self) -> f64 {
+        match self {
+            SizeClass::Large => 0.6,
+            SizeClass::Medium => 1.0,
+            SizeClass::Small => 0.0,
+        }
+    }
+
+    /// points for hitting it, the smaller the harder.
+    pub fn score(self) -> u64 {
+        match self {
+            SizeClass::Large => 20,
+            SizeClass::Medium => 50,
+            SizeClass::Small => 100,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Asteroid {
     rust_sux: MoveAblePos,
+    size: SizeClass,
     radius: f64,
 }
 
 impl Asteroid {
     pub fn bounding_box(&self) -> collision::Circle {
         return collision::Circle {
-            pos_x: self.rust_sux.pos_x,
-            pos_y: self.rust_sux.pos_y,
+            center: self.rust_sux.pos,
             radius: self.radius,
         };
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Player {
     pub rust_sux: MoveAblePos,
     radius: f64,
@@ -50,14 +292,13 @@ pub struct Player {
 impl Player {
     pub fn bounding_box(&self) -> collision::Circle {
         return collision::Circle {
-            pos_x: self.rust_sux.pos_x,
-            pos_y: self.rust_sux.pos_y,
-            radius: 2.0,
+            center: self.rust_sux.pos,
+            radius: self.radius,
         };
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 struct Bullet {
     rust_sux: MoveAblePos,
     /// amount of update time the bullet will exists for.
@@ -68,8 +309,7 @@ struct Bullet {
 impl Bullet {
     fn bounding_box(&self) -> collision::Circle {
         return collision::Circle {
-            pos_x: self.rust_sux.pos_x,
-            pos_y: self.rust_sux.pos_y,
+            center: self.rust_sux.pos,
             radius: self.radius,
         };
     }
@@ -81,12 +321,14 @@ pub struct GameState {
     pub player: Player,
     bullets: Vec<Bullet>,
     shoot_bullet_cd: i16,
-    world_width: f64,
-    world_height: f64,
+    config: GameConfig,
     // if true then the game is finished.
     pub game_over: bool,
     pub game_over_is_win: bool,
     pub score: u64,
+    // all randomness of the game comes from here, so a seed and the inputs
+    // are enough to replay a game.
+    rng: StdRng,
 }
 
 pub struct GameInput {
@@ -100,74 +342,170 @@ pub struct GameInput {
     pub thrusters: bool,
 }
 
-pub fn game_init() -> GameState {
+/// radians per update time the ship turns while a turn key is held.
+pub const TURN_SPEED: f64 = 0.5;
+
+impl GameInput {
+    /// input for the keys held down, holding both turn keys cancels out.
+    pub fn from_keys(left: bool, right: bool, thrust: bool, shoot: bool) -> Self {
+        let mut rotation = 0.0;
+        // y grows downwards on screen, so a growing direction turns clockwise.
+        if left {
+            rotation -= TURN_SPEED;
+        }
+        if right {
+            rotation += TURN_SPEED;
+        }
+        Self {
+            rotation: rotation,
+            shoot: shoot,
+            thrusters: thrust,
+        }
+    }
+}
+
+/// starts a new game, the same seed and the same inputs give the same game.
+/// seeds only replay on the rand version the game was built with.
+/// the player starts in the middle of the world, asteroids in its upper left quarter.
+pub fn game_init(config: &GameConfig, seed: u64) -> GameState {
     let mut game_state = GameState {
         asteroids: vec![],
         game_over: false,
         game_over_is_win: false,
         player: Player {
             rust_sux: MoveAblePos {
-                pos_x: 50.0,
-                pos_y: 50.0,
-                velocity: 0.0,
+                pos: Vec2::new(config.world_width * 0.5, config.world_height * 0.5),
+                velocity: Vec2::ZERO,
                 direction: 0.0,
             },
-            radius: 5.0,
+            radius: config.player_radius,
         },
         bullets: vec![],
-        world_width: 100.0,
-        world_height: 100.0,
 	shoot_bullet_cd: 0,
 	score: 0,
+	rng: StdRng::seed_from_u64(seed),
+	config: config.clone(),
     };
 
-    let mut rng = rand::thread_rng();
+    let rng = &mut game_state.rng;
 
-    for _i in 0..rng.gen_range(5, 10) {
+    let count = rng.sample(Uniform::new_inclusive(config.min_asteroids, config.max_asteroids));
+    for _i in 0..count {
+        let pos = Vec2::new(
+            rng.gen_range(config.world_width * 0.1, config.world_width * 0.5),
+            rng.gen_range(config.world_height * 0.1, config.world_height * 0.5),
+        );
+        let (min_speed, max_speed) = SizeClass::Large.speed_range(config);
+        let speed = rng.gen_range(min_speed, max_speed);
+        let direction = rng.gen_range(0.0, std::f64::consts::PI);
         game_state.asteroids.push(Asteroid {
             rust_sux: MoveAblePos {
-                pos_x: rng.gen_range(10.0, 50.0),
-                pos_y: rng.gen_range(10.0, 50.0),
-                velocity: rng.gen_range(1.0, 2.0),
-                direction: rng.gen_range(0.0, std::f64::consts::PI),
+                pos: pos,
+                velocity: Vec2::from_angle(direction) * speed,
+                direction: direction,
             },
-            radius: 8.0,
+            size: SizeClass::Large,
+            radius: SizeClass::Large.radius(config),
         });
     }
     return game_state;
 }
 
 fn update_pos(r: &mut MoveAblePos, dt: f64, world_width: f64, world_height: f64) {
-    r.pos_x += dt * r.velocity * (r.direction).cos();
-    r.pos_y += dt * r.velocity * (r.direction).sin();
+    r.pos += r.velocity * dt;
 
-    if r.pos_x > world_width {
-        r.pos_x = 0.0;
+    if r.pos.x > world_width {
+        r.pos.x = 0.0;
     }
-    if r.pos_y > world_height {
-        r.pos_y = 0.0;
+    if r.pos.y > world_height {
+        r.pos.y = 0.0;
     }
-    if r.pos_x < 0.0 {
-        r.pos_x = world_width;
+    if r.pos.x < 0.0 {
+        r.pos.x = world_width;
     }
-    if r.pos_y < 0.0 {
-        r.pos_y = world_height;
+    if r.pos.y < 0.0 {
+        r.pos.y = world_height;
     }
 }
 
+// thrust pushes along the facing, drag slows the ship down whether or not
+// it's thrusting, so turning keeps the old momentum until it wears off.
+fn accelerate_player(r: &mut MoveAblePos, config: &GameConfig, dt: f64, thrusters: bool) {
+    if thrusters {
+        r.velocity += Vec2::from_angle(r.direction) * (config.thrust_acceleration * dt);
+    }
+    r.velocity = r.velocity * (1.0 - config.drag * dt).max(0.0);
+    if r.velocity.length() > config.max_speed {
+        r.velocity = r.velocity.normalize() * config.max_speed;
+    }
+}
+
+// gap left between a new piece and the bullet that broke its asteroid.
+const SPLIT_CLEARANCE: f64 = 0.01;
+
+// pieces of an asteroid hit by bullet, each flying off the asteroid's heading
+// at a random angle within its spread. they start where the asteroid was
+// but are pushed out of the bullet so they aren't spawned inside it.
+fn split_asteroid(
+    ast: &Asteroid,
+    bullet: &collision::Circle,
+    config: &GameConfig,
+    rng: &mut StdRng,
+) -> Vec<Asteroid> {
+    let size = match ast.size.smaller() {
+        Some(size) => size,
+        None => return vec![],
+    };
+    let radius = size.radius(config);
+    let (min_speed, max_speed) = size.speed_range(config);
+    let heading = ast.rust_sux.velocity.angle();
+    let spread = ast.size.spread();
+
+    let mut pieces = Vec::new();
+    for _i in 0..ast.size.split_count() {
+        let direction = heading + rng.gen_range(-spread, spread);
+        let speed = rng.gen_range(min_speed, max_speed);
+        let mut pos = ast.rust_sux.pos + Vec2::from_angle(direction) * (ast.radius - radius);
+
+        let away = pos - bullet.center;
+        let min_distance = radius + bullet.radius + SPLIT_CLEARANCE;
+        if away.length() < min_distance {
+            // dead center on the bullet, keep going the way the piece flies.
+            let away = if away == Vec2::ZERO {
+                Vec2::from_angle(direction)
+            } else {
+                away.normalize()
+            };
+            pos = bullet.center + away * min_distance;
+        }
+
+        pieces.push(Asteroid {
+            rust_sux: MoveAblePos {
+                pos: pos,
+                velocity: Vec2::from_angle(direction) * speed,
+                direction: direction,
+            },
+            size: size,
+            radius: radius,
+        });
+    }
+    pieces
+}
+
 // called when the player wishes to shoot a bullet
 fn shoot_bullet(game_state: &mut GameState) -> () {
     let p = &game_state.player;
     let bullet = Bullet {
         // maybe could clone the players MoveAblePos
         rust_sux: MoveAblePos {
-            pos_x: p.rust_sux.pos_x,
-            pos_y: p.rust_sux.pos_y,
-            velocity: p.rust_sux.velocity + 2.0,
+            pos: p.rust_sux.pos,
+            // fired forward on top of the ship's own velocity.
+            velocity: p.rust_sux.velocity
+                + Vec2::from_angle(p.rust_sux.direction) * game_state.config.bullet_speed,
             direction: p.rust_sux.direction,
         },
-        life_time: 20.0,
-        radius: 2.0,
+        life_time: game_state.config.bullet_life_time,
+        radius: game_state.config.bullet_radius,
     };
 
     game_state.bullets.push(bullet);
@@ -184,15 +522,7 @@ fn game_state_update(game_state: &GameState, dt: f64, game_input: &GameInput) ->
     }
     if game_input.shoot && new_state.shoot_bullet_cd == 0 {
         shoot_bullet(&mut new_state);
-	// todo: what should the cd be? 
-	new_state.shoot_bullet_cd = 20;
-    }
-
-    if game_input.thrusters {
-        new_state.player.rust_sux.velocity = 2.0;
-    } else {
-        // need some sort of decay
-        new_state.player.rust_sux.velocity = 0.0;
+	new_state.shoot_bullet_cd = new_state.config.shoot_cooldown;
     }
 
     // todo: add in wrap around for bullets and asteroids and player etc.
@@ -207,20 +537,21 @@ fn game_state_update(game_state: &GameState, dt: f64, game_input: &GameInput) ->
     }
 
     let mut player = &mut new_state.player;
+    accelerate_player(&mut player.rust_sux, &game_state.config, dt, game_input.thrusters);
 
     update_pos(
         &mut player.rust_sux,
         dt,
-        game_state.world_width,
-        game_state.world_height,
+        game_state.config.world_width,
+        game_state.config.world_height,
     );
 
    for ast in new_state.asteroids.iter_mut() {
         update_pos(
             &mut ast.rust_sux,
             dt,
-            game_state.world_width,
-            game_state.world_height,
+            game_state.config.world_width,
+            game_state.config.world_height,
         );
     }
 
@@ -228,8 +559,8 @@ fn game_state_update(game_state: &GameState, dt: f64, game_input: &GameInput) ->
         update_pos(
             &mut bull.rust_sux,
             dt,
-            game_state.world_width,
-            game_state.world_height,
+            game_state.config.world_width,
+            game_state.config.world_height,
         );
         bull.life_time -= 1.0 * dt;
     }
@@ -245,39 +576,15 @@ fn game_state_update(game_state: &GameState, dt: f64, game_input: &GameInput) ->
         // todo: switch to filter on lifetime and can move retain to after this double loop?
         for bull in new_state.bullets.iter_mut() {
             if collision::collides(&ast.bounding_box(), &bull.bounding_box()) {
-                // break the asteroid into two, and give some random direction and velocity.
-                // remove the bullet.
-
-                // only make new asteroids from those that are large enough.
-                // large asteroid
-                if ast.radius > 3.0 {
-                    // add two asteroids.
-                    new_asteroids.push(Asteroid {
-                        rust_sux: MoveAblePos {
-                            pos_x: ast.rust_sux.pos_x,
-                            pos_y: ast.rust_sux.pos_y,
-                            // todo: change this at some point.
-                            velocity: ast.rust_sux.velocity - 0.1,
-                            direction: ast.rust_sux.direction,
-                        },
-                        radius: ast.radius / 2.0,
-                    });
-
-                    new_asteroids.push(Asteroid {
-                        rust_sux: MoveAblePos {
-                            pos_x: ast.rust_sux.pos_x,
-                            pos_y: ast.rust_sux.pos_y,
-                            // todo: change this at some point.
-                            velocity: ast.rust_sux.velocity + 0.1,
-                            // send this one in the opposite direction.
-                            direction: (ast.rust_sux.direction + std::f64::consts::PI * 0.5),
-                        },
-                        radius: 3.0,
-                    });
-                }
+                // break the asteroid into smaller pieces and remove the bullet.
+                new_asteroids.extend(split_asteroid(
+                    ast,
+                    &bull.bounding_box(),
+                    &new_state.config,
+                    &mut new_state.rng,
+                ));
                 deleted_aster = true;
-		// 100 points per asteroid killed. 
-		new_state.score += 100;
+                new_state.score += ast.size.score();
                 bull.life_time = 0.0;
                 break;
             }
@@ -308,47 +615,47 @@ fn game_state_update(game_state: &GameState, dt: f64, game_input: &GameInput) ->
     return new_state;
 }
 
-#[cfg(feature = "gui")] 
+// square covering the circle, pos and radius in world units.
+#[cfg(feature = "gui")]
+fn fill_body(canvas: &mut Canvas<Window>, scale: f64, pos: &Vec2, radius: f64) {
+    let size = ((radius * 2.0 * scale) as u32).max(1);
+    let _ = canvas.fill_rect(Rect::new(
+        ((pos.x - radius) * scale) as i32,
+        ((pos.y - radius) * scale) as i32,
+        size,
+        size,
+    ));
+}
+
+/// draws the world stretched over the whole canvas.
+#[cfg(feature = "gui")]
 fn game_sdl2_render(game_state: &GameState, canvas: &mut Canvas<Window>) -> () {
-    canvas.set_draw_color(Color::RGB(0, 255, 0));
-    // put this into a asteroids specific draw function.
+    let (width, _) = canvas.output_size().unwrap_or((1, 1));
+    let scale = width as f64 / game_state.config.world_width;
 
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+
+    canvas.set_draw_color(Color::RGB(255, 0, 0));
     for ast in game_state.asteroids.iter() {
-        canvas.set_draw_color(Color::RGB(255, 0, 0));
-        let p = canvas.fill_rect(Rect::new(
-            ast.rust_sux.pos_x as i32,
-            ast.rust_sux.pos_y as i32,
-            ast.radius as u32,
-            ast.radius as u32,
-        ));
-        match p {
-            Ok(_) => {}
-            Err(_) => {}
-        }
+        fill_body(canvas, scale, &ast.rust_sux.pos, ast.radius);
     }
 
+    canvas.set_draw_color(Color::RGB(125, 125, 0));
     for bull in game_state.bullets.iter() {
-        canvas.set_draw_color(Color::RGB(125, 125, 0));
-        let p = canvas.fill_rect(Rect::new(
-            bull.rust_sux.pos_x as i32,
-            bull.rust_sux.pos_y as i32,
-            bull.radius as u32,
-            bull.radius as u32,
-        ));
-        match p {
-            Ok(_) => {}
-            Err(_) => {}
-        }
+        fill_body(canvas, scale, &bull.rust_sux.pos, bull.radius);
     }
 
+    let player = &game_state.player;
     canvas.set_draw_color(Color::RGB(0, 255, 0));
-    let p = canvas.fill_rect(Rect::new(
-        game_state.player.rust_sux.pos_x as i32,
-        game_state.player.rust_sux.pos_y as i32,
-        game_state.player.radius as u32,
-        game_state.player.radius as u32,
-    ));
-} 
+    fill_body(canvas, scale, &player.rust_sux.pos, player.radius);
+    // nose of the ship, so the player can see where it is heading.
+    let nose = player.rust_sux.pos + Vec2::from_angle(player.rust_sux.direction) * (player.radius * 2.0);
+    let _ = canvas.draw_line(
+        Point::new((player.rust_sux.pos.x * scale) as i32, (player.rust_sux.pos.y * scale) as i32),
+        Point::new((nose.x * scale) as i32, (nose.y * scale) as i32),
+    );
+}
 
 #[cfg(not(feature = "gui"))]
 pub fn game_update(game_state: &GameState,
@@ -372,77 +679,621 @@ pub fn game_update(
     return new_state;
 }
 
-#[cfg(all(test, not(feature = "gui")))]
+/// how to start the playable game, see parse_args.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayOptions {
+    /// GameConfig file, the default config if None.
+    pub config: Option<PathBuf>,
+    /// seed of the first game, picked from the clock if None.
+    pub seed: Option<u64>,
+}
+
+const USAGE: &str = "usage: rminions --asteroids [--config GAME_CONFIG] [--seed N]";
+
+/// parses the command line arguments (excluding the program name).
+/// returns None if the asteroids game wasn't asked for.
+pub fn parse_args(args: &[String]) -> Result<Option<PlayOptions>, String> {
+    if !args.iter().any(|a| a == "--asteroids") {
+        return Ok(None);
+    }
+    let mut options = PlayOptions::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--asteroids" => (),
+            "--config" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--config needs a value\n{}", USAGE))?;
+                options.config = Some(PathBuf::from(value));
+            }
+            "--seed" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--seed needs a value\n{}", USAGE))?;
+                options.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", value))?,
+                );
+            }
+            _ => return Err(format!("unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok(Some(options))
+}
+
+/// update time that passes every frame.
+#[cfg(feature = "gui")]
+const FRAME_DT: f64 = 0.1;
+
+/// longest side of the window, the world is scaled to fit.
+#[cfg(feature = "gui")]
+const WINDOW_SIZE: f64 = 800.0;
+
+#[cfg(feature = "gui")]
+fn draw_text(
+    canvas: &mut WindowCanvas,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    text: &str,
+    color: Color,
+    x: i32,
+    y: i32,
+) -> Result<(), String> {
+    let surface = font
+        .render(text)
+        .blended(color)
+        .map_err(|e| e.to_string())?;
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+    let query = texture.query();
+    canvas.copy(
+        &texture,
+        None,
+        Rect::new(x, y, query.width, query.height),
+    )
+}
+
+// the last frame of the game with the result on top.
+#[cfg(feature = "gui")]
+fn draw_game_over(
+    game_state: &GameState,
+    canvas: &mut WindowCanvas,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+) -> Result<(), String> {
+    game_sdl2_render(game_state, canvas);
+    let (title, color) = if game_state.game_over_is_win {
+        ("you win", Color::RGB(0, 255, 0))
+    } else {
+        ("game over", Color::RGB(255, 0, 0))
+    };
+    let (_, height) = canvas.output_size()?;
+    let y = height as i32 / 3;
+    draw_text(canvas, texture_creator, font, title, color, 20, y)?;
+    let white = Color::RGB(255, 255, 255);
+    let score = format!("score: {}", game_state.score);
+    draw_text(canvas, texture_creator, font, &score, white, 20, y + 40)?;
+    draw_text(
+        canvas,
+        texture_creator,
+        font,
+        "r to play again, escape to quit",
+        white,
+        20,
+        y + 80,
+    )
+}
+
+#[cfg(feature = "gui")]
+fn clock_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(feature = "gui")]
+fn set_seed_title(canvas: &mut WindowCanvas, seed: u64) -> Result<(), String> {
+    canvas
+        .window_mut()
+        .set_title(&format!("asteroids (seed {})", seed))
+        .map_err(|e| e.to_string())
+}
+
+/// opens a window and plays until it is closed or escape is pressed.
+/// left/right turn, up thrusts and space shoots. every new game uses the
+/// next seed, the window title shows it so a game can be replayed with --seed.
+#[cfg(feature = "gui")]
+pub fn play(options: &PlayOptions) -> Result<(), String> {
+    let config = match &options.config {
+        Some(path) => load_config(path).map_err(|e| e.to_string())?,
+        None => GameConfig::default(),
+    };
+    let mut seed = options.seed.unwrap_or_else(clock_seed);
+
+    let sdl_context = sdl2::init()?;
+    let video_subsystem = sdl_context.video()?;
+    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string())?;
+    let scale = WINDOW_SIZE / config.world_width.max(config.world_height);
+    let window = video_subsystem
+        .window(
+            "asteroids",
+            (config.world_width * scale) as u32,
+            (config.world_height * scale) as u32,
+        )
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut canvas = window
+        .into_canvas()
+        .present_vsync()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let texture_creator = canvas.texture_creator();
+    let mut font_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    font_path.push("lazy.ttf");
+    let font = ttf_context.load_font(font_path, 32)?;
+    let mut event_pump = sdl_context.event_pump()?;
+
+    set_seed_title(&mut canvas, seed)?;
+    let mut game_state = game_init(&config, seed);
+
+    'running: loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    ..
+                } if game_state.game_over => {
+                    seed = seed.wrapping_add(1);
+                    set_seed_title(&mut canvas, seed)?;
+                    game_state = game_init(&config, seed);
+                }
+                _ => {}
+            }
+        }
+
+        if game_state.game_over {
+            draw_game_over(&game_state, &mut canvas, &texture_creator, &font)?;
+        } else {
+            let keys = event_pump.keyboard_state();
+            let game_input = GameInput::from_keys(
+                keys.is_scancode_pressed(Scancode::Left),
+                keys.is_scancode_pressed(Scancode::Right),
+                keys.is_scancode_pressed(Scancode::Up),
+                keys.is_scancode_pressed(Scancode::Space),
+            );
+            game_state = game_update(&game_state, FRAME_DT, &game_input, &mut canvas);
+            let score = format!("score: {}", game_state.score);
+            draw_text(
+                &mut canvas,
+                &texture_creator,
+                &font,
+                &score,
+                Color::RGB(255, 255, 255),
+                10,
+                10,
+            )?;
+        }
+        canvas.present();
+    }
+    Ok(())
+}
+
+// game_state_update is what game_update runs in both builds, so these run with
+// and without the gui feature.
+#[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_pos_zero_vec() {
         let mut pos_thing = MoveAblePos {
-            pos_x: 0.0,
-            pos_y: 0.0,
-            velocity: 0.0,
+            pos: Vec2::new(0.0, 0.0),
+            velocity: Vec2::ZERO,
             direction: 0.0,
         };
 
         update_pos(&mut pos_thing, 1.0, 100.0, 100.0);
-        assert_eq!(pos_thing.pos_x, 0.0);
-        assert_eq!(pos_thing.pos_y, 0.0);
+        assert_eq!(pos_thing.pos.x, 0.0);
+        assert_eq!(pos_thing.pos.y, 0.0);
     }
 
     #[test]
     fn test_pos_vec_one_zero_dir() {
         let mut pos_thing = MoveAblePos {
-            pos_x: 0.0,
-            pos_y: 0.0,
-            velocity: 1.0,
+            pos: Vec2::new(0.0, 0.0),
+            velocity: Vec2::new(1.0, 0.0),
             direction: 0.0,
         };
 
         update_pos(&mut pos_thing, 1.0, 100.0, 100.0);
-        assert_eq!(pos_thing.pos_x, 1.0);
-        assert_eq!(pos_thing.pos_y, 0.0);
+        assert_eq!(pos_thing.pos.x, 1.0);
+        assert_eq!(pos_thing.pos.y, 0.0);
     }
 
     #[test]
     fn test_pos_vec_one_90_dir() {
         let mut pos_thing = MoveAblePos {
-            pos_x: 0.0,
-            pos_y: 0.0,
-            velocity: 1.0,
+            pos: Vec2::new(0.0, 0.0),
+            velocity: Vec2::from_angle(std::f64::consts::PI * 0.5),
             direction: std::f64::consts::PI * 0.5,
         };
 
         update_pos(&mut pos_thing, 1.0, 100.0, 100.0);
-        assert!(pos_thing.pos_x < 0.00001);
-        assert!(pos_thing.pos_x > -0.0001);
-        assert!(pos_thing.pos_y == 1.0);
+        assert!(pos_thing.pos.x < 0.00001);
+        assert!(pos_thing.pos.x > -0.0001);
+        assert!(pos_thing.pos.y == 1.0);
     }
 
-    fn test_game_shoot() {
-	let game_state  = game_init();
+    // no asteroids, so nothing ends up in the ship's way.
+    fn empty_space() -> GameState {
+	let config = GameConfig {
+	    min_asteroids: 0,
+	    max_asteroids: 0,
+	    ..GameConfig::default()
+	};
+	game_init(&config, 0)
+    }
 
+    fn fly(game_state: &GameState, frames: u32, rotation: f64, thrusters: bool) -> GameState {
 	let game_input = GameInput {
+	    rotation: rotation,
+	    shoot: false,
+	    thrusters: thrusters,
+	};
+	let mut game_state = game_state.clone();
+	for _ in 0..frames {
+	    game_state = game_state_update(&game_state, 0.1, &game_input);
+	}
+	game_state
+    }
+
+    #[test]
+    fn test_thrust_accelerates_gradually() {
+	let game_state = empty_space();
+	let once = fly(&game_state, 1, 0.0, true);
+	let twice = fly(&once, 1, 0.0, true);
+	let speed = |s: &GameState| s.player.rust_sux.velocity.length();
+	assert!(speed(&once) > 0.0);
+	assert!(speed(&twice) > speed(&once));
+	assert!(speed(&twice) < game_state.config.max_speed);
+	// facing 0 flies along +x.
+	assert_eq!(twice.player.rust_sux.velocity.y, 0.0);
+	assert!(twice.player.rust_sux.pos.x > game_state.player.rust_sux.pos.x);
+    }
+
+    #[test]
+    fn test_max_speed() {
+	let game_state = fly(&empty_space(), 500, 0.0, true);
+	let speed = game_state.player.rust_sux.velocity.length();
+	assert!(speed <= game_state.config.max_speed + 1e-9);
+
+	// without drag the cap is all that stops it.
+	let mut game_state = empty_space();
+	game_state.config.drag = 0.0;
+	let game_state = fly(&game_state, 500, 0.0, true);
+	assert!((game_state.player.rust_sux.velocity.length() - game_state.config.max_speed).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_drag_slows_down() {
+	let moving = fly(&empty_space(), 20, 0.0, true);
+	let speed = moving.player.rust_sux.velocity.length();
+
+	// letting go of thrust keeps the ship drifting for a while.
+	let coasting = fly(&moving, 1, 0.0, false);
+	let coasted = coasting.player.rust_sux.velocity.length();
+	assert!(coasted > 0.0 && coasted < speed);
+	assert!(coasting.player.rust_sux.pos.x > moving.player.rust_sux.pos.x);
+
+	let stopped = fly(&moving, 300, 0.0, false);
+	assert!(stopped.player.rust_sux.velocity.length() < 1e-3);
+
+	let mut frictionless = moving.clone();
+	frictionless.config.drag = 0.0;
+	let drifting = fly(&frictionless, 50, 0.0, false);
+	assert_eq!(drifting.player.rust_sux.velocity, moving.player.rust_sux.velocity);
+    }
+
+    #[test]
+    fn test_turning_keeps_momentum() {
+	let mut game_state = fly(&empty_space(), 20, 0.0, true);
+	game_state.config.drag = 0.0;
+	let velocity = game_state.player.rust_sux.velocity;
+
+	// turning without thrust doesn't change where the ship flies.
+	let turned = fly(&game_state, 10, 1.0, false);
+	assert!(turned.player.rust_sux.direction > 0.5);
+	assert_eq!(turned.player.rust_sux.velocity, velocity);
+
+	// thrusting at a right angle adds to the old velocity instead of replacing it.
+	let mut sideways = game_state.clone();
+	sideways.player.rust_sux.direction = std::f64::consts::PI * 0.5;
+	let sideways = fly(&sideways, 1, 0.0, true);
+	assert_eq!(sideways.player.rust_sux.velocity.x, velocity.x);
+	assert!(sideways.player.rust_sux.velocity.y > 0.0);
+    }
+
+    #[test]
+    fn test_bullet_inherits_velocity() {
+	let mut game_state = empty_space();
+	game_state.player.rust_sux.velocity = Vec2::new(0.0, 1.5);
+	shoot_bullet(&mut game_state);
+	// fired along +x from a ship drifting along +y.
+	let bullet = &game_state.bullets[0];
+	assert_eq!(bullet.rust_sux.velocity, Vec2::new(game_state.config.bullet_speed, 1.5));
+    }
+
+    #[test]
+    fn test_game_shoot() {
+	// a single asteroid, with this seed it starts away from the player.
+	let config = GameConfig {
+	    min_asteroids: 1,
+	    max_asteroids: 1,
+	    ..GameConfig::default()
+	};
+	let game_state  = game_init(&config, 1);
+	assert_eq!(game_state.asteroids.len(), 1);
+
+	let mut game_input = GameInput {
 	    rotation: 0.0,
 	    shoot: false,
 	    thrusters: false,
 	};
 
-	let mut new_state = game_update(&game_state, 0.1, &game_input);
+	let new_state = game_state_update(&game_state, 0.1, &game_input);
 	assert_eq!(new_state.bullets.len(), 0);
 
-	// currently can't assert that bullet shoots cause it could collide with an asteroid
-	// todo: update game init to take number of asteroids then can test if bullets shoot or not. 
+	game_input.shoot = true;
+	let new_state = game_state_update(&new_state, 0.1, &game_input);
+	assert_eq!(new_state.bullets.len(), 1);
+	assert_eq!(new_state.score, 0);
+
+	// still on cooldown.
+	let new_state = game_state_update(&new_state, 0.1, &game_input);
+	assert_eq!(new_state.bullets.len(), 1);
+	assert_eq!(new_state.shoot_bullet_cd, config.shoot_cooldown - 1);
+    }
+
+    // every field, and the rng has to be at the same point of its sequence.
+    fn assert_same_state(a: &GameState, b: &GameState) {
+	assert_eq!(a.asteroids, b.asteroids);
+	assert_eq!(a.player, b.player);
+	assert_eq!(a.bullets, b.bullets);
+	assert_eq!(a.shoot_bullet_cd, b.shoot_bullet_cd);
+	assert_eq!(a.game_over, b.game_over);
+	assert_eq!(a.game_over_is_win, b.game_over_is_win);
+	assert_eq!(a.score, b.score);
+	assert_eq!(a.rng.clone().gen::<u64>(), b.rng.clone().gen::<u64>());
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+	let config = GameConfig::default();
+	let mut first = game_init(&config, 42);
+	let mut second = game_init(&config, 42);
+	assert_same_state(&first, &second);
+
+	for frame in 0..500 {
+	    let game_input = GameInput {
+		rotation: if frame % 50 < 25 { 1.0 } else { -0.5 },
+		shoot: frame % 3 == 0,
+		thrusters: frame % 7 < 4,
+	    };
+	    first = game_state_update(&first, 0.5, &game_input);
+	    second = game_state_update(&second, 0.5, &game_input);
+	    assert_same_state(&first, &second);
+	}
+	// the run should have hit something, or it tells little about splitting.
+	assert!(first.score > 0);
+
+	assert_ne!(game_init(&config, 42).asteroids, game_init(&config, 43).asteroids);
+    }
+
+    fn asteroid(size: SizeClass, pos: Vec2, velocity: Vec2) -> Asteroid {
+	Asteroid {
+	    rust_sux: MoveAblePos {
+		pos: pos,
+		velocity: velocity,
+		direction: velocity.angle(),
+	    },
+	    size: size,
+	    radius: size.radius(&GameConfig::default()),
+	}
+    }
+
+    #[test]
+    fn test_split_sizes() {
+	let config = GameConfig::default();
+	let mut rng = StdRng::seed_from_u64(3);
+	let bullet = collision::Circle {
+	    center: Vec2::new(45.0, 50.0),
+	    radius: config.bullet_radius,
+	};
+
+	let large = asteroid(SizeClass::Large, Vec2::new(50.0, 50.0), Vec2::new(0.0, 1.0));
+	let pieces = split_asteroid(&large, &bullet, &config, &mut rng);
+	assert_eq!(pieces.len() as u32, SizeClass::Large.split_count());
+	let (min_speed, max_speed) = SizeClass::Medium.speed_range(&config);
+	for piece in pieces.iter() {
+	    assert_eq!(piece.size, SizeClass::Medium);
+	    assert_eq!(piece.radius, config.asteroid_radius * 0.5);
+	    let speed = piece.rust_sux.velocity.length();
+	    assert!(speed >= min_speed - 1e-9 && speed < max_speed + 1e-9);
+	    // veers off the parent's heading by at most the spread.
+	    let veer = piece.rust_sux.velocity.angle() - large.rust_sux.velocity.angle();
+	    assert!(veer.abs() <= SizeClass::Large.spread());
+	}
+
+	let medium = pieces[0].clone();
+	let pieces = split_asteroid(&medium, &bullet, &config, &mut rng);
+	assert_eq!(pieces.len(), 2);
+	assert!(pieces.iter().all(|p| p.size == SizeClass::Small));
+
+	let small = pieces[0].clone();
+	assert!(split_asteroid(&small, &bullet, &config, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn test_split_clears_bullet() {
+	let config = GameConfig::default();
+	let mut rng = StdRng::seed_from_u64(9);
+	let large = asteroid(SizeClass::Large, Vec2::new(50.0, 50.0), Vec2::new(1.0, 0.0));
+	// bullets right in the middle, on the rim and just off where the pieces would start.
+	for center in [Vec2::new(50.0, 50.0), Vec2::new(57.0, 50.0), Vec2::new(54.0, 51.0)].iter() {
+	    let bullet = collision::Circle {
+		center: *center,
+		radius: config.bullet_radius,
+	    };
+	    for _ in 0..20 {
+		for piece in split_asteroid(&large, &bullet, &config, &mut rng).iter() {
+		    assert!(!collision::collides(&piece.bounding_box(), &bullet));
+		}
+	    }
+	}
+    }
+
+    #[test]
+    fn test_score_per_size() {
+	let mut game_state = empty_space();
+	game_state.asteroids = vec![
+	    asteroid(SizeClass::Small, Vec2::new(20.0, 20.0), Vec2::ZERO),
+	    asteroid(SizeClass::Large, Vec2::new(80.0, 80.0), Vec2::ZERO),
+	];
+	let game_input = GameInput {
+	    rotation: 0.0,
+	    shoot: false,
+	    thrusters: false,
+	};
+
+	// a bullet sitting on the small one.
+	shoot_bullet(&mut game_state);
+	game_state.bullets[0].rust_sux.pos = Vec2::new(20.0, 20.0);
+	game_state.bullets[0].rust_sux.velocity = Vec2::ZERO;
+	let game_state = game_state_update(&game_state, 0.1, &game_input);
+	assert_eq!(game_state.score, SizeClass::Small.score());
+	assert_eq!(game_state.asteroids.len(), 1);
+
+	let mut game_state = game_state;
+	shoot_bullet(&mut game_state);
+	game_state.bullets[0].rust_sux.pos = Vec2::new(80.0, 80.0);
+	game_state.bullets[0].rust_sux.velocity = Vec2::ZERO;
+	let game_state = game_state_update(&game_state, 0.1, &game_input);
+	assert_eq!(game_state.score, SizeClass::Small.score() + SizeClass::Large.score());
+	assert_eq!(game_state.asteroids.len(), 2);
+	assert!(game_state.asteroids.iter().all(|a| a.size == SizeClass::Medium));
+	assert!(game_state.bullets.is_empty());
+	assert!(!game_state.game_over);
     }
 
     #[test]
     fn test_shoot_bullet() {
-	let mut game_state  = game_init();
+	let mut game_state  = game_init(&GameConfig::default(), 0);
 
 	shoot_bullet(&mut game_state);
 
 	assert_eq!(game_state.bullets.len(), 1);
 
     }
+
+    #[test]
+    fn test_input_from_keys() {
+	let input = GameInput::from_keys(true, false, true, false);
+	assert_eq!(input.rotation, -TURN_SPEED);
+	assert!(input.thrusters);
+	assert!(!input.shoot);
+
+	let input = GameInput::from_keys(true, true, false, true);
+	assert_eq!(input.rotation, 0.0);
+	assert!(input.shoot);
+	assert_eq!(GameInput::from_keys(false, true, false, false).rotation, TURN_SPEED);
+    }
+
+    #[test]
+    fn test_parse_args() {
+	let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+
+	assert_eq!(parse_args(&args(&["--headless", "--ticks", "5"])), Ok(None));
+	assert_eq!(parse_args(&args(&["--asteroids"])), Ok(Some(PlayOptions::default())));
+	assert_eq!(
+	    parse_args(&args(&["--asteroids", "--seed", "7", "--config", "hard.ron"])),
+	    Ok(Some(PlayOptions {
+		config: Some(PathBuf::from("hard.ron")),
+		seed: Some(7),
+	    }))
+	);
+	assert!(parse_args(&args(&["--asteroids", "--seed", "x"])).is_err());
+	assert!(parse_args(&args(&["--asteroids", "--ticks", "5"])).is_err());
+    }
+
+    #[test]
+    fn test_config_from_str() {
+	let config = config_from_str("(min_asteroids: 2, max_asteroids: 3, player_radius: 4.0)").unwrap();
+	assert_eq!(config.min_asteroids, 2);
+	assert_eq!(config.max_asteroids, 3);
+	// left out fields keep their defaults.
+	assert_eq!(config.world_width, GameConfig::default().world_width);
+
+	let game_state = game_init(&config, 5);
+	assert!(game_state.asteroids.len() >= 2 && game_state.asteroids.len() <= 3);
+	assert_eq!(game_state.player.bounding_box().radius, 4.0);
+	assert_eq!(game_state.player.rust_sux.pos, Vec2::new(50.0, 50.0));
+    }
+
+    #[test]
+    fn test_config_errors() {
+	for bad in ["(min_asteroids: 4, max_asteroids: 3)",
+		    "(world_width: 0.0)",
+		    "(asteroid_min_speed: 2.0, asteroid_max_speed: 2.0)",
+		    "(shoot_cooldown: -1)",
+		    "(max_asteroids: 4294967295)"].iter() {
+	    match config_from_str(bad) {
+		Err(ConfigError::Invalid(_)) => {}
+		other => panic!("{} should be invalid, got {:?}", bad, other),
+	    }
+	}
+	let bad_floats = [
+	    GameConfig { bullet_speed: 0.0, ..GameConfig::default() },
+	    GameConfig { bullet_speed: f64::NAN, ..GameConfig::default() },
+	    GameConfig { bullet_radius: f64::INFINITY, ..GameConfig::default() },
+	    GameConfig { player_radius: -1.0, ..GameConfig::default() },
+	    GameConfig { max_speed: f64::INFINITY, ..GameConfig::default() },
+	    GameConfig { asteroid_max_speed: f64::INFINITY, ..GameConfig::default() },
+	    GameConfig { drag: f64::NAN, ..GameConfig::default() },
+	];
+	for config in bad_floats.iter() {
+	    match config.validate() {
+		Err(ConfigError::Invalid(_)) => {}
+		other => panic!("{:?} should be invalid, got {:?}", config, other),
+	    }
+	}
+	let most = format!("(min_asteroids: {0}, max_asteroids: {0})", MAX_ASTEROIDS);
+	assert_eq!(game_init(&config_from_str(&most).unwrap(), 0).asteroids.len() as u32, MAX_ASTEROIDS);
+	match config_from_str("(asteroids: 3)") {
+	    Err(ConfigError::Parse(_)) => {}
+	    other => panic!("expected a parse error, got {:?}", other),
+	}
+    }
+
+    #[test]
+    fn test_config_presets() {
+	let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+	path.push("levels/asteroids");
+	for name in ["easy.ron", "hard.ron"].iter() {
+	    let config = load_config(&path.join(name)).unwrap();
+	    let game_state = game_init(&config, 0);
+	    assert!(game_state.asteroids.len() as u32 >= config.min_asteroids);
+	}
+    }
 }
 
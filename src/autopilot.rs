@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use crate::entity_manager::Entity;
+use crate::game_state::{Command, GameInput, GameState, Position, UserCommand};
+use crate::resource::Resource;
+use crate::utils::Path;
+
+/// hand written "player" that keeps the hive harvesting.
+/// spawns a unit early on and points every programmable unit at the first
+/// mine that still has something in it.
+pub struct Autopilot {
+    // each programed unit gets a target
+    // first entity is the actor, second is the "target"
+    programmed_units: HashMap<Entity, Entity>,
+    current_target_entity: Option<Entity>,
+}
+
+pub fn strip_empties(x: &mut HashMap<Entity, Entity>, value: &Entity) {
+    let tmp = x.clone();
+    let empties = tmp.iter().filter(|&(_, &v)| v.0 == value.0).map(|(k, _)| k);
+
+    for k in empties {
+        x.remove(k);
+    }
+}
+
+#[allow(dead_code)]
+pub fn generate_pathing_program(path: &Path) -> Vec<Command> {
+    let mut program = Vec::<Command>::new();
+
+    let _pos_offset_dist: f32 = 1.0;
+    let _speed = 0.5; // meters per second
+    let _tile_width = 16;
+
+    for p in path.path_points.iter() {
+        let current_pos = Position::new(p.0, p.1);
+        program.push(Command::MoveP(current_pos));
+    }
+
+    return program;
+}
+
+/// walks to the target, harvests and brings the load back to the hive.
+pub fn program_harvest_unit(
+    _entity: &Entity,
+    target_entity: &Entity,
+    target_pos: &Position,
+    resource: Resource,
+    hive: &Entity,
+    hive_pos: &Position,
+) -> Vec<Command> {
+    // should be like get programable units.
+    let mut prog = Vec::new();
+    prog.push(Command::MoveD(Position::new(
+        target_pos.get_x(),
+        target_pos.get_y(),
+    )));
+    prog.push(Command::Harvest(target_entity.clone(), resource));
+    prog.push(Command::MoveD(Position::new(
+        hive_pos.get_x(),
+        hive_pos.get_y(),
+    )));
+    prog.push(Command::Deposit(*hive));
+    return prog;
+}
+
+impl Autopilot {
+    pub fn new() -> Self {
+        Self {
+            programmed_units: HashMap::new(),
+            current_target_entity: None,
+        }
+    }
+
+    /// fills in game_input for the given frame based on the current state.
+    pub fn update(&mut self, current_state: &GameState, frame: u32, game_input: &mut GameInput) {
+        if frame == 2 {
+            game_input.create_unit = true;
+        }
+
+        // nothing to bring resources back to.
+        let hive = match current_state.get_hive() {
+            Some(hive) => hive,
+            None => return,
+        };
+        let hive_pos = current_state.get_entity_position(&hive);
+
+        // if the current target entity runes out of mins set it to none so we can find a new target.
+        match self.current_target_entity {
+            Some(current_ent) => match current_state.get_mineable_count(&current_ent) {
+                None => {
+                    self.current_target_entity = None;
+                }
+                Some(amount) => {
+                    if amount == 0 {
+                        strip_empties(&mut self.programmed_units, &current_ent);
+                        self.current_target_entity = None;
+                    }
+                }
+            },
+            None => {}
+        }
+
+        // no current target.
+        if !self.current_target_entity.is_some() {
+            for e in current_state.get_mineable_nodes() {
+                // don't consider hive.
+                if *e != hive {
+                    match current_state.get_mineable_count(e) {
+                        Some(amount) => {
+                            if amount > 0 {
+                                self.current_target_entity = Some(*e);
+                                break;
+                            }
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        if let Some(target) = self.current_target_entity {
+            let mine_pos = current_state.get_entity_position(&target);
+            let resource = current_state
+                .solid_containers
+                .get(&target)
+                .and_then(|t| t.first_held())
+                .unwrap_or(Resource::Iron);
+
+            for e in current_state.get_programable_units() {
+                if !self.programmed_units.contains_key(e) {
+                    let prog =
+                        program_harvest_unit(e, &target, &mine_pos, resource, &hive, &hive_pos);
+
+                    game_input
+                        .user_commands
+                        .push(UserCommand::LoadProgram(*e, prog));
+
+                    self.programmed_units.insert(*e, target);
+                }
+            }
+        }
+    }
+}
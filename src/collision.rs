@@ -1,15 +1,14 @@
 #![allow(dead_code)]
 /// helper functions and objects for 2d collision detect.
+use serde::{Deserialize, Serialize};
 
-struct Point {
-    x: f64,
-    y: f64,
-}
+use crate::vec2::Vec2;
+
+type Point = Vec2;
 
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Circle {
-    pub pos_x: f64,
-    pub pos_y: f64,
+    pub center: Vec2,
     pub radius: f64,
 }
 
@@ -100,9 +99,7 @@ fn line_intersect(p_a1: &Point, p_a2: &Point, p_b1: &Point, p_b2: &Point) -> boo
 }
 
 pub fn collides(circle_one: &Circle, circle_two: &Circle) -> bool {
-    let dist_x = circle_one.pos_x - circle_two.pos_x;
-    let dist_y = circle_one.pos_y - circle_two.pos_y;
-    let dist = ((dist_x * dist_x) + (dist_y * dist_y)).sqrt();
+    let dist = circle_one.center.distance(&circle_two.center);
     return dist <= circle_one.radius + circle_two.radius;
 }
 
@@ -113,26 +110,22 @@ mod tests {
     #[test]
     fn test_colliding_circles() {
         let circle_one = Circle {
-            pos_x: 0.0,
-            pos_y: 0.0,
+            center: Vec2::new(0.0, 0.0),
             radius: 1.0,
         };
 
         let circle_two = Circle {
-            pos_x: 0.0,
-            pos_y: 0.0,
+            center: Vec2::new(0.0, 0.0),
             radius: 1.0,
         };
 
         let circle_three = Circle {
-            pos_x: 0.5,
-            pos_y: 0.0,
+            center: Vec2::new(0.5, 0.0),
             radius: 1.0,
         };
 
         let circle_four = Circle {
-            pos_x: 10.0,
-            pos_y: 10.0,
+            center: Vec2::new(10.0, 10.0),
             radius: 2.0,
         };
 
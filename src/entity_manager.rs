@@ -1,13 +1,36 @@
 use std::collections::HashSet;
 
-#[derive(PartialEq, Copy, Clone, Debug, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+
+/// the low 32 bits are the index, the high 32 bits the generation.
+/// destroyed indexes are handed out again with the next generation, so a handle
+/// kept around after its entity was destroyed never matches the new entity.
+/// entities of generation 0 are just their index, Entity(3) is the third entity created.
+#[derive(PartialEq, Copy, Clone, Debug, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Entity(pub u64);
 
+impl Entity {
+    pub fn from_parts(index: u32, generation: u32) -> Entity {
+        Entity((generation as u64) << 32 | index as u64)
+    }
+
+    pub fn index(&self) -> u32 {
+        self.0 as u32
+    }
+
+    /// how often the index was reused before this entity got it.
+    pub fn generation(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct EntityManager {
     // todo: remove pub
     pub entities: HashSet<Entity>,
     current_entity_id: Entity,
+    // destroyed entities, their indexes are reused by create, last destroyed first.
+    free: Vec<Entity>,
 }
 
 impl EntityManager {
@@ -16,16 +39,65 @@ impl EntityManager {
         EntityManager {
             current_entity_id: Entity(1),
             entities: HashSet::new(),
+            free: Vec::new(),
         }
     }
 
     pub fn create(&mut self) -> Entity {
-        let new_id = Entity(self.current_entity_id.0);
+        let new_id = match self.free.pop() {
+            Some(old) => Entity::from_parts(old.index(), old.generation() + 1),
+            None => {
+                let id = self.current_entity_id;
+                self.current_entity_id = Entity(self.current_entity_id.0 + 1);
+                id
+            }
+        };
         self.entities.insert(new_id);
-        self.current_entity_id = Entity(self.current_entity_id.0 + 1);
         return new_id;
     }
 
+    /// forgets the entity, returns false if it was already destroyed or never created.
+    /// the components of the entity aren't touched, see GameState::destroy_entity.
+    pub fn destroy(&mut self, entity: &Entity) -> bool {
+        if !self.entities.remove(entity) {
+            return false;
+        }
+        // an index that ran out of generations is never handed out again.
+        if entity.generation() < u32::MAX {
+            self.free.push(*entity);
+        }
+        return true;
+    }
+
+    /// false for handles of destroyed entities, even if their index was reused.
+    pub fn is_alive(&self, entity: &Entity) -> bool {
+        self.entities.contains(entity)
+    }
+
+    /// rebuilds a manager from a saved set of entities, next_id is the id
+    /// that create hands out once the free entities are used up.
+    pub fn from_parts(
+        entities: HashSet<Entity>,
+        next_id: Entity,
+        free: Vec<Entity>,
+    ) -> EntityManager {
+        EntityManager {
+            current_entity_id: next_id,
+            entities: entities,
+            free: free,
+        }
+    }
+
+    /// the first never used index, as a generation 0 entity.
+    pub fn next_id(&self) -> Entity {
+        self.current_entity_id
+    }
+
+    /// destroyed entities whose indexes will be reused, create takes the last one first.
+    pub fn free_entities(&self) -> &[Entity] {
+        &self.free
+    }
+
     // returns total number of current available entities.
     #[allow(dead_code)]
     pub fn count(&self) -> usize {
@@ -62,4 +134,48 @@ mod tests {
         let p = EntityManager::new();
         assert_eq!(p.count(), 0);
     }
+
+    #[test]
+    fn entity_parts() {
+        let e = Entity::from_parts(7, 2);
+        assert_eq!(e.index(), 7);
+        assert_eq!(e.generation(), 2);
+        assert_eq!(Entity::from_parts(7, 0), Entity(7));
+    }
+
+    #[test]
+    fn entity_destroy_reuses_index() {
+        let mut p = EntityManager::new();
+        let first = p.create();
+        let second = p.create();
+
+        assert!(p.destroy(&first));
+        assert!(!p.destroy(&first));
+        assert!(!p.is_alive(&first));
+        assert!(p.is_alive(&second));
+        assert_eq!(p.count(), 1);
+
+        let reused = p.create();
+        assert_eq!(reused.index(), first.index());
+        assert_eq!(reused.generation(), 1);
+        assert_ne!(reused, first);
+        // the stale handle doesn't match the new entity.
+        assert!(!p.is_alive(&first));
+        assert!(p.is_alive(&reused));
+
+        // nothing left to reuse, back to fresh indexes.
+        assert_eq!(p.create(), Entity(3));
+        assert!(!p.destroy(&Entity(40)));
+    }
+
+    #[test]
+    fn entity_out_of_generations_is_retired() {
+        let worn = Entity::from_parts(1, u32::MAX);
+        let mut entities = HashSet::new();
+        entities.insert(worn);
+        let mut p = EntityManager::from_parts(entities, Entity(2), Vec::new());
+
+        assert!(p.destroy(&worn));
+        assert_eq!(p.create(), Entity(2));
+    }
 }
@@ -0,0 +1,163 @@
+//! Things that happened during a game_update call.
+//!
+//! game_update hands back every event of the tick it ran, in the order they
+//! happened. Events are plain data so the UI, tests and replays can consume
+//! them, `Display` gives a one line description for logs.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity_manager::Entity;
+use crate::game_state::{Position, UserCommandError};
+use crate::resource::Resource;
+
+/// why moving resources between two entities didn't happen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TransferFailure {
+    /// the entities are further than 2 tiles apart.
+    TooFar,
+    /// the source has none of the resource left to give.
+    SourceEmpty,
+    /// the receiver can't hold any more of the resource.
+    TargetFull,
+    /// the receiver carries as much weight as it can.
+    Overloaded,
+    /// one of the entities has no container or position.
+    NoContainer,
+}
+
+impl fmt::Display for TransferFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransferFailure::TooFar => write!(f, "too far away"),
+            TransferFailure::SourceEmpty => write!(f, "nothing left"),
+            TransferFailure::TargetFull => write!(f, "no room left"),
+            TransferFailure::Overloaded => write!(f, "carrying too much"),
+            TransferFailure::NoContainer => write!(f, "no container"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameEventKind {
+    /// a unit was created next to the hive.
+    UnitSpawned { unit: Entity },
+    /// create_unit was asked for but the spawn tile is taken.
+    SpawnBlocked { position: Position },
+    /// unit took amount from node.
+    Harvested {
+        unit: Entity,
+        node: Entity,
+        resource: Resource,
+        amount: u32,
+    },
+    HarvestFailed {
+        unit: Entity,
+        node: Entity,
+        reason: TransferFailure,
+    },
+    /// unit handed amount over to target.
+    Deposited {
+        unit: Entity,
+        target: Entity,
+        resource: Resource,
+        amount: u32,
+    },
+    DepositFailed {
+        unit: Entity,
+        target: Entity,
+        reason: TransferFailure,
+    },
+    /// the last resource of a mine was harvested.
+    MineDepleted { node: Entity },
+    /// unit couldn't move to position.
+    MoveBlocked { unit: Entity, position: Position },
+    /// a program, command or script sent to the unit wasn't loaded.
+    ProgramRejected {
+        unit: Entity,
+        error: UserCommandError,
+    },
+    /// the unit can't pay for its next command, it waits until recharged at the hive.
+    OutOfEnergy { unit: Entity },
+    /// the unit's program jumped to a label it doesn't have, the unit halts.
+    UnknownLabel { unit: Entity, label: String },
+    /// the unit's lua program raised an error or ran out of budget, it stops running.
+    ScriptFailed { unit: Entity, message: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameEvent {
+    /// GameState::tick of the update the event happened in.
+    pub tick: u32,
+    pub kind: GameEventKind,
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tick {}: ", self.tick)?;
+        match &self.kind {
+            GameEventKind::UnitSpawned { unit } => write!(f, "unit {} spawned", unit.0),
+            GameEventKind::SpawnBlocked { position } => write!(
+                f,
+                "spawn blocked at {}, {}",
+                position.get_x(),
+                position.get_y()
+            ),
+            GameEventKind::Harvested {
+                unit,
+                node,
+                resource,
+                amount,
+            } => write!(
+                f,
+                "unit {} harvested {} {} from {}",
+                unit.0, amount, resource, node.0
+            ),
+            GameEventKind::HarvestFailed { unit, node, reason } => {
+                write!(
+                    f,
+                    "unit {} failed to harvest {}: {}",
+                    unit.0, node.0, reason
+                )
+            }
+            GameEventKind::Deposited {
+                unit,
+                target,
+                resource,
+                amount,
+            } => write!(
+                f,
+                "unit {} deposited {} {} into {}",
+                unit.0, amount, resource, target.0
+            ),
+            GameEventKind::DepositFailed {
+                unit,
+                target,
+                reason,
+            } => write!(
+                f,
+                "unit {} failed to deposit into {}: {}",
+                unit.0, target.0, reason
+            ),
+            GameEventKind::MineDepleted { node } => write!(f, "mine {} is empty", node.0),
+            GameEventKind::MoveBlocked { unit, position } => write!(
+                f,
+                "unit {} can't move to {}, {}",
+                unit.0,
+                position.get_x(),
+                position.get_y()
+            ),
+            GameEventKind::ProgramRejected { unit, error } => {
+                write!(f, "program for unit {} rejected: {}", unit.0, error)
+            }
+            GameEventKind::OutOfEnergy { unit } => write!(f, "unit {} ran out of energy", unit.0),
+            GameEventKind::UnknownLabel { unit, label } => {
+                write!(f, "unit {} jumped to unknown label {}", unit.0, label)
+            }
+            GameEventKind::ScriptFailed { unit, message } => {
+                write!(f, "unit {} script stopped: {}", unit.0, message)
+            }
+        }
+    }
+}
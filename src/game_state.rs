@@ -1,29 +1,42 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
 use sdl2::render::{Canvas, TextureCreator};
 
 // todo: remove unused imports.
-use sdl2;
+#[cfg(feature = "gui")]
 use sdl2::pixels::Color;
-use sdl2::rect::Rect;
+#[cfg(feature = "gui")]
+use sdl2::rect::{Point, Rect};
 
 // Texture, TextureCreator
+#[cfg(feature = "gui")]
 use sdl2::video::Window;
 
+#[cfg(feature = "gui")]
 use crate::circles::create_circle_texture;
-use crate::collision::Circle;
+use crate::collision::{collides, Circle};
 use crate::entity_manager::{Entity, EntityManager};
-use crate::utils::manhat_distance;
-use crate::utils::uclid_distance;
+use crate::events::{GameEvent, GameEventKind, TransferFailure};
+use crate::level;
+use crate::level::LevelError;
+use crate::resource::Resource;
+use crate::utils::{
+    axial_to_offset, hex_distance, hex_neighbours, manhat_distance, offset_to_axial,
+};
+use crate::vec2::Vec2;
 
 //
 // visual width, visual height
 // meter width, meter height
 
 /// @brief a positional offset centered on the x/y pos
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PosOffset {
     // todo: change these to u32
     x: f32,
@@ -32,7 +45,7 @@ pub struct PosOffset {
 
 // Components
 /// Position component, tracks the x, y of an entity.
-#[derive(Default, Debug, Clone, PartialEq)]
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Position {
     x: u32,
     y: u32,
@@ -51,6 +64,25 @@ pub fn world_to_display(pos: &Position, pixels_per_meter: u16) -> (i32, i32) {
     return (x_pos, y_pos);
 }
 
+/// world_to_display for a world of the given layout.
+/// hex rows are pushed together so the hexagons touch, odd rows go half a tile right.
+pub fn layout_to_display(pos: &Position, layout: GridLayout, pixels_per_meter: u16) -> (i32, i32) {
+    match layout {
+        GridLayout::Square => world_to_display(pos, pixels_per_meter),
+        GridLayout::Hex => {
+            let row_shift = if pos.y % 2 == 1 { 0.5 } else { 0.0 };
+            let tile_pos_x = pos.x as f32 + (pos.offset.x / 100.0) + row_shift;
+            let tile_pos_y = (pos.y as f32 + (pos.offset.y / 100.0)) * HEX_ROW_HEIGHT;
+            let x_pos: i32 = (tile_pos_x * pixels_per_meter as f32) as i32;
+            let y_pos: i32 = (tile_pos_y * pixels_per_meter as f32) as i32;
+            (x_pos, y_pos)
+        }
+    }
+}
+
+/// distance between hex rows for hexes 1 tile wide, sqrt(3) / 2.
+const HEX_ROW_HEIGHT: f32 = 0.866_025_4;
+
 impl Position {
     pub fn new(x: u32, y: u32) -> Self {
         Self {
@@ -67,94 +99,285 @@ impl Position {
         self.y
     }
 
-    pub fn distance(&self, other: &Self) -> f32 {
-        let p_x1 = self.x as f32 * 100.0 + self.offset.x;
-        let p_y1 = self.y as f32 * 100.0 + self.offset.y;
-        let p_x2 = other.x as f32 * 100.0 + other.offset.x;
-        let p_y2 = other.y as f32 * 100.0 + other.offset.y;
+    /// axial (q, r) coordinates of the tile on a GridLayout::Hex world.
+    pub fn hex(&self) -> (i32, i32) {
+        offset_to_axial(self.x, self.y)
+    }
 
-        return uclid_distance(p_x1, p_y1, p_x2, p_y2);
+    /// tile at axial hex coordinates, None if it is left of or above the world.
+    pub fn from_hex(q: i32, r: i32) -> Option<Self> {
+        axial_to_offset(q, r).map(|(x, y)| Self::new(x, y))
+    }
+
+    /// distance in hundredths of a tile, the unit of the offsets.
+    pub fn distance(&self, other: &Self) -> f32 {
+        (self.to_world().distance(&other.to_world()) * 100.0) as f32
     }
 
     /// @brief position, but without default
+    /// offsets of 100 or more carry into the tile, negative offsets borrow from it.
+    /// positions can't go below tile 0, anything further left or up stops at 0.
     pub fn new_with_offset(x: u32, y: u32, x_off: f32, y_off: f32) -> Self {
-        let mut p = Self::new(x, y);
+        Self::from_parts(x as i64, y as i64, x_off, y_off)
+    }
 
-        if x_off >= 100.0 {
-            let x_carry_over = x_off / 100.0;
-            p.x += x_carry_over as u32;
-            p.offset.x = x_off % 100.0;
-        } else if x_off < 0.0 {
-            // subtraction
+    // tiles plus offsets of any size, carried and clamped into the range of a Position.
+    fn from_parts(x: i64, y: i64, x_off: f32, y_off: f32) -> Self {
+        let (x, x_off) = clamp_tile(carry(x, x_off));
+        let (y, y_off) = clamp_tile(carry(y, y_off));
+        Self {
+            x: x,
+            y: y,
+            offset: PosOffset { x: x_off, y: y_off },
+        }
+    }
 
-            let tmp_x_off = x_off.abs();
+    pub fn add(&self, other: &Self) -> Self {
+        Self::from_parts(
+            self.x as i64 + other.x as i64,
+            self.y as i64 + other.y as i64,
+            self.offset.x + other.offset.x,
+            self.offset.y + other.offset.y,
+        )
+    }
 
-            if p.offset.x < tmp_x_off {
-                if p.x < (tmp_x_off / 100.0) as u32 {
-                    panic!("Can't carry as {} < {} ", p.x, tmp_x_off);
-                }
-                // todo: how to get rid of this whileloop?
-                while p.offset.x < tmp_x_off {
-                    p.offset.x += 100.0;
-                    p.x -= 1;
-                }
-                p.offset.x -= tmp_x_off;
-            }
-        } else {
-            p.offset.x = x_off;
-        }
+    /// stops at tile 0, see to_world for differences that go negative.
+    pub fn sub(&self, other: &Self) -> Self {
+        Self::from_parts(
+            self.x as i64 - other.x as i64,
+            self.y as i64 - other.y as i64,
+            self.offset.x - other.offset.x,
+            self.offset.y - other.offset.y,
+        )
+    }
 
-        if y_off >= 100.0 {
-            let y_carry_over = y_off / 100.0;
-            p.y += y_carry_over as u32;
-        } else if y_off < 0.0 {
-            // subtraction
+    /// both coordinates times factor, stops at tile 0 like sub.
+    pub fn scale(&self, factor: f64) -> Self {
+        Self::from_world_clamped(self.to_world() * factor)
+    }
 
-            let tmp_y_off = y_off.abs();
+    /// the position in continuous world coordinates.
+    pub fn to_world(&self) -> WorldPos {
+        WorldPos::new(
+            self.x as f64 + self.offset.x as f64 / 100.0,
+            self.y as f64 + self.offset.y as f64 / 100.0,
+        )
+    }
 
-            if p.offset.y < tmp_y_off {
-                if p.y < (tmp_y_off / 100.0) as u32 {
-                    panic!("Can't carry as {} < {} ", p.y, tmp_y_off);
-                }
-                // todo: how to get rid of this whileloop?
-                while p.offset.y < tmp_y_off {
-                    p.offset.y += 100.0;
-                    p.y -= 1;
-                }
-                p.offset.y -= tmp_y_off;
-            }
-        } else {
-            p.offset.y = y_off;
+    /// None for points left of or above tile 0, or past the last u32 tile.
+    pub fn from_world(pos: WorldPos) -> Option<Self> {
+        let max = u32::MAX as f64 + 1.0;
+        if !(pos.x >= 0.0 && pos.x < max && pos.y >= 0.0 && pos.y < max) {
+            return None;
         }
-        return p;
+        Some(Self::from_world_clamped(pos))
     }
 
-    pub fn add(&self, other: &Self) -> Self {
-        let x_off = other.offset.x + self.offset.x;
-        let y_off = other.offset.y + self.offset.y;
+    /// like from_world, points outside of the range stop at its edge.
+    pub fn from_world_clamped(pos: WorldPos) -> Self {
+        let (x, x_off) = split_world(pos.x);
+        let (y, y_off) = split_world(pos.y);
+        Self::from_parts(x, y, x_off, y_off)
+    }
+}
 
-        Position::new_with_offset(self.x + other.x, self.y + other.y, x_off, y_off)
+// moves whole tiles out of offset so it ends up in [0, 100).
+fn carry(tile: i64, offset: f32) -> (i64, f32) {
+    if !offset.is_finite() {
+        return (tile, 0.0);
+    }
+    let carried = (offset / 100.0).floor();
+    let mut tile = tile.saturating_add(carried as i64);
+    let mut rest = offset - carried * 100.0;
+    // rounding can leave the offset a hair outside of the range.
+    if rest >= 100.0 {
+        rest -= 100.0;
+        tile = tile.saturating_add(1);
+    }
+    if rest < 0.0 {
+        rest = 0.0;
     }
+    (tile, rest)
+}
 
-    // todo: add subtraction.
-    #[allow(dead_code)]
-    pub fn sub(&self, _other: Self) -> Self {
-        todo!();
+fn clamp_tile((tile, offset): (i64, f32)) -> (u32, f32) {
+    if tile < 0 {
+        (0, 0.0)
+    } else if tile > u32::MAX as i64 {
+        (u32::MAX, 0.0)
+    } else {
+        (tile as u32, offset)
+    }
+}
+
+// world coordinate as tile and offset, the offset isn't carried yet.
+fn split_world(value: f64) -> (i64, f32) {
+    if !value.is_finite() {
+        return (0, 0.0);
     }
+    let tile = value.floor();
+    (tile as i64, ((value - tile) * 100.0) as f32)
 }
 
+/// signed, continuous world coordinates in tiles, 1.5 is half way through tile 1.
+/// unlike Position it goes below 0, so differences and directions can be worked out
+/// without clamping. see Position::to_world and Position::from_world.
+pub type WorldPos = Vec2;
+
 /// How much energy a specific entity contains.
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EnergyLevel {
-    value: u32,
+    pub(crate) value: u32,
+    // recharging stops here.
+    pub(crate) max: u32,
+    // set once the entity couldn't pay for a command, cleared by recharging.
+    pub(crate) depleted: bool,
+}
+
+/// energy a unit starts with and recharges up to.
+pub const UNIT_MAX_ENERGY: u32 = 100;
+
+/// units within this many tiles (manhattan) of the hive recharge.
+pub const HIVE_RECHARGE_RANGE: u32 = 1;
+
+/// what commands cost and how fast the hive recharges, part of the level.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnergyConfig {
+    /// per tile entered by MoveP.
+    pub(crate) move_p: u32,
+    /// per tile entered by MoveD.
+    pub(crate) move_d: u32,
+    /// per successful Harvest.
+    pub(crate) harvest: u32,
+    /// per successful Deposit.
+    pub(crate) deposit: u32,
+    /// energy per tick given to units near the hive.
+    pub(crate) recharge: u32,
+}
+
+impl Default for EnergyConfig {
+    fn default() -> Self {
+        Self {
+            move_p: 1,
+            move_d: 1,
+            harvest: 2,
+            deposit: 1,
+            recharge: 5,
+        }
+    }
+}
+
+impl EnergyConfig {
+    /// energy a unit needs to run the command for a tick.
+    pub fn cost(&self, command: &Command) -> u32 {
+        match command {
+            Command::MoveP(_) => self.move_p,
+            Command::MoveD(_) => self.move_d,
+            Command::Harvest(..) => self.harvest,
+            Command::Deposit(_) => self.deposit,
+            _ => 0,
+        }
+    }
 }
 
+/// most load (see Resource::weight) a unit can carry.
+pub const UNIT_CARGO_CAPACITY: u32 = 10;
+
+/// MoveD speed of an empty unit, in centimeters per second.
+pub const UNIT_BASE_SPEED: f32 = 10.0;
+
+/// share of the speed a fully loaded unit loses, it moves at half speed.
+pub const FULL_LOAD_SLOWDOWN: f32 = 0.5;
+
+/// energy level at or below which EnergyLow holds.
+pub const ENERGY_LOW_THRESHOLD: u32 = 10;
+
+/// bounding circle radius of units, in tiles.
+pub const UNIT_RADIUS: f64 = 0.3;
+
+/// bounding circle radius of the hive and resource nodes, in tiles.
+pub const NODE_RADIUS: f64 = 0.5;
+
 /// Indicates the item that a individual can hold of something.
 /// Storage of solids.
-#[derive(Default, Clone, Debug)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SolidContainer {
-    iron_count: u32,
-    copper_count: u32,
+    // amount held of each resource, resources without an entry are at 0.
+    pub(crate) contents: BTreeMap<Resource, u32>,
+    // most that can be held of a resource, no entry means no limit.
+    pub(crate) capacities: BTreeMap<Resource, u32>,
+    // most total weight that can be held, None means no limit.
+    pub(crate) max_load: Option<u32>,
+}
+
+impl SolidContainer {
+    pub fn get(&self, resource: Resource) -> u32 {
+        *self.contents.get(&resource).unwrap_or(&0)
+    }
+
+    /// sets the amount held, ignoring the capacity.
+    pub fn set(&mut self, resource: Resource, amount: u32) {
+        if amount == 0 {
+            self.contents.remove(&resource);
+        } else {
+            self.contents.insert(resource, amount);
+        }
+    }
+
+    /// how much more of the resource fits, limited by both its capacity and the load.
+    pub fn space(&self, resource: Resource) -> u32 {
+        let by_capacity = match self.capacities.get(&resource) {
+            Some(capacity) => capacity.saturating_sub(self.get(resource)),
+            None => u32::MAX - self.get(resource),
+        };
+        by_capacity.min(self.load_space() / resource.weight())
+    }
+
+    /// weight of everything held.
+    pub fn load(&self) -> u32 {
+        self.contents
+            .iter()
+            .map(|(r, amount)| r.weight() * amount)
+            .sum()
+    }
+
+    /// weight that can still be added.
+    pub fn load_space(&self) -> u32 {
+        match self.max_load {
+            Some(max) => max.saturating_sub(self.load()),
+            None => u32::MAX,
+        }
+    }
+
+    /// true if not a single unit of any resource fits anymore.
+    pub fn is_full(&self) -> bool {
+        Resource::ALL.iter().all(|r| self.space(*r) == 0)
+    }
+
+    /// adds up to amount, returns how much was actually added.
+    pub fn add(&mut self, resource: Resource, amount: u32) -> u32 {
+        let added = amount.min(self.space(resource));
+        self.set(resource, self.get(resource) + added);
+        return added;
+    }
+
+    /// removes up to amount, returns how much was actually removed.
+    pub fn take(&mut self, resource: Resource, amount: u32) -> u32 {
+        let taken = amount.min(self.get(resource));
+        self.set(resource, self.get(resource) - taken);
+        return taken;
+    }
+
+    /// first resource (in Resource order) there is some of.
+    pub fn first_held(&self) -> Option<Resource> {
+        self.contents.keys().next().cloned()
+    }
+
+    /// everything held, regardless of type.
+    pub fn total(&self) -> u32 {
+        self.contents.values().sum()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -169,32 +392,80 @@ pub enum Direction {
     West,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Command {
     /// used for moving to the next point.
     MoveP(Position),
     #[allow(dead_code)]
     MoveD(Position),
 
-    /// used for extracting resources from the provided position.
-    Harvest(Entity),
+    /// used for extracting the resource from the provided entity.
+    Harvest(Entity, Resource),
     /// Used for dropping the current holding items off
     Deposit(Entity), // just the inverse of harvest is needed?
+
+    // control flow, these don't take a tick to run.
+    /// marks a jump target, does nothing itself.
+    Label(String),
+    /// continues the program at the label.
+    Jump(String),
+    /// continues at the label if the condition holds, otherwise with the next command.
+    JumpIf(Condition, String),
+    /// stops the program until a new one is loaded.
+    Halt,
+
+    /// keeps the unit idle for the number of ticks (at least one).
+    Wait(u32),
+}
+
+/// unit state a JumpIf can test.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    /// the unit can't take a single unit of any resource anymore.
+    CargoFull,
+    /// the unit holds nothing.
+    CargoEmpty,
+    /// the mine has nothing left to harvest.
+    MineEmpty(Entity),
+    /// the unit's energy is at or below ENERGY_LOW_THRESHOLD.
+    EnergyLow,
 }
 
 /// Collision component, tracks if the the entity should collide.
 /// Collision only occurs if both entity have collection.
-#[derive(Clone, Default)]
+/// bounding_box is in tiles, its center is relative to the entity's position.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Collision {
-    value: bool,
+    pub(crate) value: bool,
     // todo: change type to generic list of shapes, such as rectangles /circles etc.
-    bounding_box: Circle,
+    pub(crate) bounding_box: Circle,
+}
+
+impl Collision {
+    /// colliding circle of the given radius centered on the entity.
+    pub fn solid(radius: f64) -> Self {
+        Self {
+            value: true,
+            bounding_box: Circle {
+                center: Vec2::ZERO,
+                radius: radius,
+            },
+        }
+    }
+
+    /// the bounding box placed at pos, in tiles.
+    fn circle_at(&self, pos: &Position) -> Circle {
+        Circle {
+            center: pos.to_world() + self.bounding_box.center,
+            radius: self.bounding_box.radius,
+        }
+    }
 }
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MineableNode {
-    current_amount: u32,
-    initial_amount: u32,
+    pub(crate) current_amount: u32,
+    pub(crate) initial_amount: u32,
 }
 
 #[derive(Clone, Debug)]
@@ -209,7 +480,7 @@ impl<T> ComponentManager<T>
 where
     T: Default,
 {
-    fn new() -> ComponentManager<T> {
+    pub(crate) fn new() -> ComponentManager<T> {
         ComponentManager {
             components: Vec::new(),
             // after moving Entity and Entity manager a type is required????
@@ -219,7 +490,7 @@ where
     }
 
     /// Checks if an the associated entity contains the component of type T
-    fn contains(&self, entity: &Entity) -> bool {
+    pub(crate) fn contains(&self, entity: &Entity) -> bool {
         match self.lookup.get(entity) {
             Some(_) => true,
             None => false,
@@ -227,14 +498,22 @@ where
     }
 
     /// Creates a component of type T and associates it to the entity
-    fn create(&mut self, entity: &Entity) -> &mut T {
-        if self.contains(entity) {
-            todo!();
+    /// an entity that already has one gets it reset to the default.
+    pub(crate) fn create(&mut self, entity: &Entity) -> &mut T {
+        // T must define a default value.
+        self.create_with(entity, T::default())
+    }
+
+    /// Create a component with the initial value as specified by init_v.
+    /// replaces the component the entity already has.
+    pub(crate) fn create_with(&mut self, entity: &Entity, init_v: T) -> &mut T {
+        if let Some(&entity_index) = self.lookup.get(entity) {
+            self.components[entity_index] = init_v;
+            return &mut self.components[entity_index];
         }
 
         let entity_index = self.components.len();
-        // T must define a default value.
-        self.components.push(T::default());
+        self.components.push(init_v);
 
         self.entities.push(*entity);
         self.lookup.insert(*entity, entity_index);
@@ -242,13 +521,7 @@ where
         return &mut self.components[entity_index];
     }
 
-    /// Create a component with the initial value as specified by init_v.
-    #[allow(dead_code)]
-    fn create_with(&mut self, _entity: &Entity, _init_v: &T) -> &mut T {
-        todo!();
-    }
-
-    fn get(&self, entity: &Entity) -> Option<&T> {
+    pub(crate) fn get(&self, entity: &Entity) -> Option<&T> {
         match self.lookup.get(entity) {
             Some(&t) => Some(&self.components[t]),
             None => None,
@@ -257,42 +530,277 @@ where
 
     /// Returns the associated component for the entity provided.
     /// Returns None if the entity does not have such a component.
-    fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+    pub(crate) fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
         match self.lookup.get(entity) {
             Some(&t) => Some(&mut self.components[t]),
             None => None,
         }
     }
 
-    /// removes the entity and its corresponding component.
-    fn remove(&mut self, entity: &Entity) {
-        match self.lookup.get(entity) {
-            Some(&entity_index) => {
-                self.components.swap_remove(entity_index);
-                self.lookup.remove(entity);
-            }
-            None => {}
+    /// iterates over every entity that has this component, in storage order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Entity, &T)> {
+        self.entities.iter().zip(self.components.iter())
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (&Entity, &mut T)> {
+        self.entities.iter().zip(self.components.iter_mut())
+    }
+
+    /// the components of two different entities at once, for moving things between them.
+    /// None if either entity has no component or both are the same entity.
+    pub(crate) fn get_pair_mut(&mut self, a: &Entity, b: &Entity) -> Option<(&mut T, &mut T)> {
+        let (a_index, b_index) = match (self.lookup.get(a), self.lookup.get(b)) {
+            (Some(&a_index), Some(&b_index)) if a_index != b_index => (a_index, b_index),
+            _ => return None,
+        };
+        if a_index < b_index {
+            let (low, high) = self.components.split_at_mut(b_index);
+            Some((&mut low[a_index], &mut high[0]))
+        } else {
+            let (low, high) = self.components.split_at_mut(a_index);
+            Some((&mut high[0], &mut low[b_index]))
+        }
+    }
+
+    /// every entity that has both this component and one in other, in this manager's storage order.
+    pub(crate) fn join<'a, U>(
+        &'a self,
+        other: &'a ComponentManager<U>,
+    ) -> impl Iterator<Item = (&'a Entity, &'a T, &'a U)> {
+        self.iter()
+            .filter_map(move |(e, t)| other.lookup.get(e).map(|&i| (e, t, &other.components[i])))
+    }
+
+    /// like join, with this manager's components mutable.
+    pub(crate) fn join_mut<'a, U>(
+        &'a mut self,
+        other: &'a ComponentManager<U>,
+    ) -> impl Iterator<Item = (&'a Entity, &'a mut T, &'a U)> {
+        self.iter_mut()
+            .filter_map(move |(e, t)| other.lookup.get(e).map(|&i| (e, t, &other.components[i])))
+    }
+
+    /// every entity that has this component and one in both second and third.
+    #[allow(dead_code)]
+    pub(crate) fn join3<'a, U, V>(
+        &'a self,
+        second: &'a ComponentManager<U>,
+        third: &'a ComponentManager<V>,
+    ) -> impl Iterator<Item = (&'a Entity, &'a T, &'a U, &'a V)> {
+        self.join(second).filter_map(move |(e, t, u)| {
+            third
+                .lookup
+                .get(e)
+                .map(|&i| (e, t, u, &third.components[i]))
+        })
+    }
+
+    /// removes the entity and its corresponding component, returns false if it had none.
+    /// the last component takes the freed slot, so storage order changes.
+    pub(crate) fn remove(&mut self, entity: &Entity) -> bool {
+        let entity_index = match self.lookup.remove(entity) {
+            Some(t) => t,
+            None => return false,
         };
+        self.components.swap_remove(entity_index);
+        self.entities.swap_remove(entity_index);
+        if let Some(moved) = self.entities.get(entity_index) {
+            self.lookup.insert(*moved, entity_index);
+        }
+        return true;
+    }
+}
+
+/// the part of a ComponentManager that doesn't depend on the component type,
+/// so every store of a GameState can be gone through at once.
+pub(crate) trait ComponentStore {
+    fn remove_entity(&mut self, entity: &Entity) -> bool;
+}
+
+impl<T> ComponentStore for ComponentManager<T>
+where
+    T: Default,
+{
+    fn remove_entity(&mut self, entity: &Entity) -> bool {
+        self.remove(entity)
+    }
+}
+
+/// shape of the world's tiles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridLayout {
+    /// square tiles, neighbours share an edge (see manhat_distance).
+    #[default]
+    Square,
+    /// pointy topped hexagons in rows, odd rows are pushed half a tile to the right.
+    /// x, y stay the tile column and row, see Position::hex for hex coordinates.
+    Hex,
+}
+
+impl GridLayout {
+    /// number of steps between two tiles, ranges such as harvesting and hive range use this.
+    pub fn tile_distance(&self, x1: u32, y1: u32, x2: u32, y2: u32) -> u32 {
+        match self {
+            GridLayout::Square => manhat_distance(x1, y1, x2, y2),
+            GridLayout::Hex => hex_distance(x1, y1, x2, y2),
+        }
+    }
+}
+
+/// size of the playable area in tiles, positions run from 0 to width - 1 / height - 1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldBounds {
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub layout: GridLayout,
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self {
+            width: 20,
+            height: 20,
+            layout: GridLayout::Square,
+        }
+    }
+}
+
+/// most tiles levels and snapshots may give a world.
+pub const MAX_WORLD_TILES: u32 = 1 << 20;
+
+impl WorldBounds {
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// width * height, None if that doesn't fit in a u32.
+    pub fn tile_count(&self) -> Option<u32> {
+        self.width.checked_mul(self.height)
     }
 }
 
 // details a single tile aspect,
 // is the "flooring" that items can stand on.
 // items can not be standing on two tiles at the same time.
-#[derive(Clone, Debug)]
-pub struct Tile {}
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tile {
+    /// units can't move onto tiles that aren't walkable.
+    pub walkable: bool,
+    /// multiplies the energy of moves onto the tile and the cost of paths through it.
+    /// between 1 and MAX_TERRAIN_COST.
+    pub terrain_cost: u32,
+    /// resource found on the tile, set for the tiles of resource nodes.
+    pub deposit: Option<Resource>,
+}
+
+/// highest terrain_cost levels and snapshots may give a tile.
+pub const MAX_TERRAIN_COST: u32 = 1000;
+
+impl Default for Tile {
+    fn default() -> Self {
+        Self {
+            walkable: true,
+            terrain_cost: 1,
+            deposit: None,
+        }
+    }
+}
+
+/// every tile of the world, row by row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileMap {
+    bounds: WorldBounds,
+    tiles: Vec<Tile>,
+}
+
+impl TileMap {
+    /// map of the given size with every tile walkable.
+    pub fn new(bounds: WorldBounds) -> Self {
+        let count = bounds
+            .tile_count()
+            .expect("world has more tiles than fit in a u32") as usize;
+        Self {
+            bounds: bounds,
+            tiles: vec![Tile::default(); count],
+        }
+    }
+
+    pub fn bounds(&self) -> &WorldBounds {
+        &self.bounds
+    }
+
+    pub fn layout(&self) -> GridLayout {
+        self.bounds.layout
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        if self.bounds.contains(x, y) {
+            Some((y * self.bounds.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<&Tile> {
+        self.index(x, y).map(|i| &self.tiles[i])
+    }
+
+    pub fn get_mut(&mut self, x: u32, y: u32) -> Option<&mut Tile> {
+        match self.index(x, y) {
+            Some(i) => Some(&mut self.tiles[i]),
+            None => None,
+        }
+    }
+
+    /// false for tiles off the map.
+    pub fn is_walkable(&self, x: u32, y: u32) -> bool {
+        self.get(x, y).map_or(false, |t| t.walkable)
+    }
+
+    /// 1 for tiles off the map.
+    pub fn terrain_cost(&self, x: u32, y: u32) -> u32 {
+        self.get(x, y).map_or(1, |t| t.terrain_cost.max(1))
+    }
+
+    /// tiles that aren't Tile::default, by row then column.
+    pub fn changed_tiles(&self) -> Vec<((u32, u32), Tile)> {
+        let default = Tile::default();
+        let width = self.bounds.width;
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t != default)
+            .map(|(i, t)| ((i as u32 % width, i as u32 / width), t.clone()))
+            .collect()
+    }
+}
 
 // holds a single frame of the game at a given point.
 #[derive(Clone)]
 pub struct GameState {
-    entity_manager: EntityManager,
-    positions: ComponentManager<Position>,
-    collision: ComponentManager<Collision>,
-    energy_levels: ComponentManager<EnergyLevel>,
-    hive_entity: Option<Entity>,
-    iron_mines: ComponentManager<MineableNode>,
-    memory: ComponentManager<Memory>,
-    solid_containers: ComponentManager<SolidContainer>,
+    pub(crate) entity_manager: EntityManager,
+    pub(crate) positions: ComponentManager<Position>,
+    pub(crate) collision: ComponentManager<Collision>,
+    pub(crate) energy_levels: ComponentManager<EnergyLevel>,
+    pub(crate) hive_entity: Option<Entity>,
+    pub(crate) iron_mines: ComponentManager<MineableNode>,
+    pub(crate) memory: ComponentManager<Memory>,
+    pub(crate) solid_containers: ComponentManager<SolidContainer>,
+    pub(crate) tile_map: TileMap,
+    pub(crate) energy: EnergyConfig,
+    // number of game_update calls this state went through.
+    pub(crate) tick: u32,
+}
+
+/// result of a single game_update call.
+pub struct GameUpdate {
+    pub state: GameState,
+    /// everything that happened during the update, in order.
+    pub events: Vec<GameEvent>,
+    /// one entry per GameInput::user_commands, in the same order.
+    /// everything after the first error is Err(UserCommandError::Skipped).
+    pub command_results: Vec<Result<(), UserCommandError>>,
 }
 
 impl GameState {
@@ -306,6 +814,9 @@ impl GameState {
             iron_mines: ComponentManager::<MineableNode>::new(),
             memory: ComponentManager::<Memory>::new(),
             solid_containers: ComponentManager::<SolidContainer>::new(),
+            tile_map: TileMap::new(WorldBounds::default()),
+            energy: EnergyConfig::default(),
+            tick: 0,
         }
     }
 
@@ -319,17 +830,67 @@ impl GameState {
                 p.x = x;
                 p.y = y;
 
-                let mut f = self
-                    .solid_containers
+                self.solid_containers
                     .create(&(self.hive_entity.expect("Faile to build hive")));
-                f.iron_count = 0;
-                f.copper_count = 0;
+                *self
+                    .collision
+                    .create(&(self.hive_entity.expect("Faile to build hive"))) =
+                    Collision::solid(NODE_RADIUS);
             }
             _ => (),
         };
         return ();
     }
 
+    // every component store, destroy_entity relies on new stores being listed here.
+    fn component_stores(&mut self) -> [&mut dyn ComponentStore; 6] {
+        [
+            &mut self.positions,
+            &mut self.collision,
+            &mut self.energy_levels,
+            &mut self.iron_mines,
+            &mut self.memory,
+            &mut self.solid_containers,
+        ]
+    }
+
+    /// removes the entity and all of its components, returns false for entities
+    /// that don't exist (anymore). destroying the hive leaves the game without one.
+    /// programs pointing at the entity stay as they are, their commands fail from now on.
+    pub fn destroy_entity(&mut self, entity: &Entity) -> bool {
+        if !self.entity_manager.destroy(entity) {
+            return false;
+        }
+        for store in self.component_stores().iter_mut() {
+            store.remove_entity(entity);
+        }
+        if self.hive_entity == Some(*entity) {
+            self.hive_entity = None;
+        }
+        return true;
+    }
+
+    /// false for handles of destroyed entities.
+    pub fn is_alive(&self, entity: &Entity) -> bool {
+        self.entity_manager.is_alive(entity)
+    }
+
+    pub fn get_hive(&self) -> Option<Entity> {
+        self.hive_entity
+    }
+
+    pub fn get_world_bounds(&self) -> &WorldBounds {
+        self.tile_map.bounds()
+    }
+
+    pub fn get_tile_map(&self) -> &TileMap {
+        &self.tile_map
+    }
+
+    pub fn get_tick(&self) -> u32 {
+        self.tick
+    }
+
     pub fn has_hive(&self) -> bool {
         match self.hive_entity {
             Some(_) => true,
@@ -354,13 +915,18 @@ impl GameState {
         return self.solid_containers.entities.iter().collect();
     }
 
+    /// everything the entity holds, regardless of resource.
     pub fn get_mineable_count(&self, mineable_entity: &Entity) -> Option<u32> {
         match self.solid_containers.get(mineable_entity) {
-            Some(t) => Some(t.iron_count),
+            Some(t) => Some(t.total()),
             None => None,
         }
     }
 
+    pub fn get_resource_count(&self, entity: &Entity, resource: Resource) -> Option<u32> {
+        self.solid_containers.get(entity).map(|t| t.get(resource))
+    }
+
     // testing / debug
     pub fn string(&self) -> String {
         let mut res = String::new();
@@ -385,7 +951,10 @@ impl GameState {
             }
             match self.solid_containers.get(&entity) {
                 Some(t) => {
-                    res.push_str(&format!("\t I: {}\n", t.iron_count));
+                    res.push_str(&format!("\t I: {}\n", t.get(Resource::Iron)));
+                    if t.get(Resource::Copper) > 0 {
+                        res.push_str(&format!("\t C: {}\n", t.get(Resource::Copper)));
+                    }
                 }
                 None => {}
             }
@@ -395,22 +964,34 @@ impl GameState {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Memory {
     // current value of program counter
     // points to the "next" command to run, thus is updated after the command
     // runs succesfully.
-    program_counter: u32,
-    commands: Vec<Command>,
+    pub(crate) program_counter: u32,
+    pub(crate) commands: Vec<Command>,
+    // lua source of the unit's program when it runs a script instead of a
+    // command list. the script feeds commands one at a time, see
+    // scripting::UnitScripts, and commands only holds the ones not yet finished.
+    pub(crate) script: Option<String>,
+    // goes up on every LoadScript, so loading the same source again restarts it.
+    #[serde(default)]
+    pub(crate) script_generation: u32,
+    // ticks left of the Wait command being run.
+    pub(crate) wait_remaining: Option<u32>,
+    // set by Halt, nothing runs until a new program is loaded.
+    pub(crate) halted: bool,
 }
 
-impl Default for Memory {
-    fn default() -> Memory {
-        println!("Memory default impl");
-        Memory {
-            program_counter: 0,
-            commands: Vec::<Command>::new(),
-        }
+impl Memory {
+    /// clears out the running program state, used when a new program is loaded.
+    fn reset(&mut self) {
+        self.commands.clear();
+        self.program_counter = 0;
+        self.script = None;
+        self.wait_remaining = None;
+        self.halted = false;
     }
 }
 
@@ -419,13 +1000,58 @@ pub enum UserCommand {
     LoadCommand(Entity, Command),
     /// clears and sets an entire program to the corresponding entity.
     LoadProgram(Entity, Vec<Command>),
+    /// replaces the entities program with a lua script, see scripting::UnitScripts.
+    LoadScript(Entity, String),
+}
+
+impl UserCommand {
+    pub fn entity(&self) -> Entity {
+        match self {
+            UserCommand::LoadCommand(e, _) => *e,
+            UserCommand::LoadProgram(e, _) => *e,
+            UserCommand::LoadScript(e, _) => *e,
+        }
+    }
+}
+
+/// units only take commands within this many tiles (manhattan) of the hive.
+pub const HIVE_COMMAND_RANGE: u32 = 5;
+
+/// why a UserCommand wasn't applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UserCommandError {
+    /// the unit is further than HIVE_COMMAND_RANGE from the hive.
+    OutOfHiveRange { unit: Entity, distance: u32 },
+    /// the entity doesn't exist.
+    UnknownEntity(Entity),
+    /// the entity has no memory (or no position) so it can't run programs.
+    NotProgrammable(Entity),
+    /// LoadProgram without commands or LoadScript without source.
+    EmptyProgram(Entity),
+    /// an earlier command of the same GameInput failed, so this one wasn't looked at.
+    Skipped,
+}
+
+impl fmt::Display for UserCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserCommandError::OutOfHiveRange { unit, distance } => write!(
+                f,
+                "unit {} is {} tiles from the hive (range is {})",
+                unit.0, distance, HIVE_COMMAND_RANGE
+            ),
+            UserCommandError::UnknownEntity(e) => write!(f, "entity {} doesn't exist", e.0),
+            UserCommandError::NotProgrammable(e) => write!(f, "entity {} isn't programmable", e.0),
+            UserCommandError::EmptyProgram(e) => write!(f, "empty program for unit {}", e.0),
+            UserCommandError::Skipped => write!(f, "skipped after an earlier error"),
+        }
+    }
 }
 
 pub struct GameInput {
-    // todo.
-    // initial idea is game input is a order set of commands that are processed in order
-    // invalid commands would thus return errors back and result in no further commands
-    // being processed.
+    // game input is a order set of commands that are processed in order,
+    // the first invalid command returns an error and the remaining commands
+    // aren't processed, see GameUpdate::command_results.
     pub create_unit: bool,
     pub create_hive: bool,
     pub user_commands: Vec<UserCommand>,
@@ -445,7 +1071,13 @@ pub fn game_init() -> GameState {
     return GameState::new();
 }
 
-// todo: allow for loading from file.
+/// builds the game state described by the level file at path.
+/// see the level module for the file format.
+pub fn game_load_from(path: &std::path::Path) -> Result<GameState, LevelError> {
+    level::load(path)
+}
+
+// default level used when no level file is given.
 pub fn game_load() -> GameState {
     let mut new_game_state = GameState::new();
 
@@ -457,7 +1089,7 @@ pub fn game_load() -> GameState {
 
     {
         let mut p = new_game_state.solid_containers.create(&iron_e);
-        p.iron_count = 2;
+        p.set(Resource::Iron, 2);
     }
     {
         let mut p = new_game_state.positions.create(&iron_e);
@@ -465,16 +1097,14 @@ pub fn game_load() -> GameState {
         p.y = 5;
     }
 
-    {
-        let mut p = new_game_state.collision.create(&iron_e);
-        p.value = true;
-    }
+    *new_game_state.collision.create(&iron_e) = Collision::solid(NODE_RADIUS);
+    new_game_state.tile_map.get_mut(10, 5).unwrap().deposit = Some(Resource::Iron);
 
     let iron_2_two = new_game_state.entity_manager.create();
 
     {
         let mut p = new_game_state.solid_containers.create(&iron_2_two);
-        p.iron_count = 900;
+        p.set(Resource::Iron, 900);
     }
     {
         let mut p = new_game_state.positions.create(&iron_2_two);
@@ -482,10 +1112,8 @@ pub fn game_load() -> GameState {
         p.y = 10;
     }
 
-    {
-        let mut p = new_game_state.collision.create(&iron_2_two);
-        p.value = true;
-    }
+    *new_game_state.collision.create(&iron_2_two) = Collision::solid(NODE_RADIUS);
+    new_game_state.tile_map.get_mut(5, 10).unwrap().deposit = Some(Resource::Iron);
 
     // unit
     // let new_entity = new_game_state.entity_manager.create();
@@ -502,144 +1130,360 @@ pub fn game_load() -> GameState {
     return new_game_state;
 }
 
+/// first walkable tile next to the hive, the one below it if possible.
+fn spawn_tile(tile_map: &TileMap, hive_pos: &Position) -> Option<Position> {
+    let (x, y) = (hive_pos.get_x() as i64, hive_pos.get_y() as i64);
+    // the tile below is a neighbour in both layouts.
+    let mut candidates = vec![(x, y + 1)];
+    match tile_map.layout() {
+        GridLayout::Square => candidates.extend(
+            [(1, 0), (0, -1), (-1, 0)]
+                .iter()
+                .map(|(dx, dy)| (x + dx, y + dy)),
+        ),
+        GridLayout::Hex => candidates.extend(
+            hex_neighbours(x as u32, y as u32)
+                .into_iter()
+                .map(|(x, y)| (x as i64, y as i64)),
+        ),
+    }
+    candidates
+        .into_iter()
+        .filter(|(x, y)| *x >= 0 && *y >= 0)
+        .map(|(x, y)| (x as u32, y as u32))
+        .find(|(x, y)| tile_map.is_walkable(*x, *y))
+        .map(|(x, y)| Position::new(x, y))
+}
+
 /// @breif helper function for spawning units as the corresponding position
-fn spawn_unit(game_state: &mut GameState, p: Position) {
+pub(crate) fn spawn_unit(game_state: &mut GameState, p: Position) -> Entity {
     let new_entity = game_state.entity_manager.create();
     // todo: add collision detection to where the spawn point is located relative to the hive.
-    let pos_component = game_state.positions.create(&new_entity);
-    *pos_component = p;
+    game_state.positions.create_with(&new_entity, p);
     game_state.memory.create(&new_entity);
-    game_state.collision.create(&new_entity);
-    game_state.solid_containers.create(&new_entity);
+    game_state
+        .collision
+        .create_with(&new_entity, Collision::solid(UNIT_RADIUS));
+    game_state.solid_containers.create(&new_entity).max_load = Some(UNIT_CARGO_CAPACITY);
+    game_state.energy_levels.create_with(
+        &new_entity,
+        EnergyLevel {
+            value: UNIT_MAX_ENERGY,
+            max: UNIT_MAX_ENERGY,
+            depleted: false,
+        },
+    );
+    return new_entity;
 }
 
 // todo: harvest might be just switchable to "transfer from one entity to another"
 // harvest entity is the entity that is being harvested.
-// harvest type marks which item to pull out of the harvest entity
+// resource marks which item to pull out of the harvest entity
+// returns the amount moved over.
 fn harvest_system(
     entity: &Entity,
     positions: &mut ComponentManager<Position>,
     solid_containers: &mut ComponentManager<SolidContainer>,
     harvest_entity: &Entity,
-    harvest_type: &str,
-) {
-    println!("Harvest system");
-    // todo: do the error handling.
-    let entity_pos = positions.get(entity).unwrap();
-    let harvest_pos = positions.get(harvest_entity).unwrap();
-
-    if manhat_distance(entity_pos.x, entity_pos.y, harvest_pos.x, harvest_pos.y) > 2 {
-        println!("Failed to harvest due to being to far away");
-        return;
+    resource: Resource,
+    layout: GridLayout,
+) -> Result<u32, TransferFailure> {
+    let (entity_pos, harvest_pos) = match (positions.get(entity), positions.get(harvest_entity)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Err(TransferFailure::NoContainer),
+    };
+
+    if layout.tile_distance(entity_pos.x, entity_pos.y, harvest_pos.x, harvest_pos.y) > 2 {
+        return Err(TransferFailure::TooFar);
     }
 
     // amount checking.
-    match solid_containers.get(harvest_entity) {
-        Some(harvest_container) => {
-            if harvest_type == "iron" {
-                if harvest_container.iron_count <= 0 {
-                    // harvest entity is out of resources.
-                    println!("Failed to harvest since mine is empty");
-                    return;
-                }
+    match (
+        solid_containers.get(entity),
+        solid_containers.get(harvest_entity),
+    ) {
+        (Some(entity_container), Some(harvest_container)) => {
+            if harvest_container.get(resource) == 0 {
+                // harvest entity is out of resources.
+                return Err(TransferFailure::SourceEmpty);
+            }
+            if entity_container.load_space() < resource.weight() {
+                return Err(TransferFailure::Overloaded);
+            }
+            if entity_container.space(resource) == 0 {
+                return Err(TransferFailure::TargetFull);
             }
         }
-        None => {
-            // harvest entity doesn't have an associated container to pull from.
-            println!("Nothing to harvest");
-            return;
-        }
-    }
-
-    {
-        let mut harvest_c = solid_containers.get_mut(harvest_entity).unwrap();
-        if harvest_type == "iron" {
-            harvest_c.iron_count -= 1;
-        } else {
-            panic!("Unknown harvest type");
+        _ => {
+            // one side doesn't have an associated container to move between.
+            return Err(TransferFailure::NoContainer);
         }
     }
 
-    {
-        let mut entity_c = solid_containers.get_mut(entity).unwrap();
-        if harvest_type == "iron" {
-            entity_c.iron_count += 1;
-        } else {
-            panic!("Unknown harvest type");
-        }
-    }
+    let (entity_container, harvest_container) =
+        match solid_containers.get_pair_mut(entity, harvest_entity) {
+            Some(t) => t,
+            // an entity can't harvest from itself.
+            None => return Err(TransferFailure::NoContainer),
+        };
+    let taken = harvest_container.take(resource, 1);
+    entity_container.add(resource, taken);
 
-    // can't do this due to barrow system.
-    // // todo: error handling.
-    // match solid_containers.get_mut(entity) {
-    // 	Some(mut entity_container) => {
-    // 	    match solid_containers.get_mut(harvest_entity) {
-    // 		Some(mut harvest_container) => {
-    // 		    // harvest_container.iron_count -= 1;
-    // 		    // entity_container.iron_count += 1;
-    // 		},
-    // 		None => (),
-    // 	    }
-    // 	},
-    // 	None => (),
-    // };
+    return Ok(taken);
+}
 
-    // // ensure entity_container isn't full.
-    // // if entity_container.
+fn find_label(commands: &[Command], label: &str) -> Option<usize> {
+    commands.iter().position(|c| match c {
+        Command::Label(l) => l == label,
+        _ => false,
+    })
 }
 
-// not all items that have positions are moveable, should there exist moveable componetns?
-// currently not a good way to tie component X first entity to its other components. ./shrug
-fn movement_system(
+fn check_condition(
     entity: &Entity,
-    positions: &mut ComponentManager<Position>,
-    collisions: &mut ComponentManager<Collision>,
-    new_pos: Position,
-) {
-    // collision movement system.
-    let mut is_colliding = false;
-    // for e_collision in collisions.entities.iter() {
-    //     // only do collision detection on non myself entities.
-    //     // todo: some rust iterator thing for this?
-    //     if e_collision != entity {
-    //         match positions.get(&e_collision) {
-    //             Some(t) => {
-    //                 // need bounding box collision rather than point collision.
-    //                 if t.x == new_pos.x && t.y == new_pos.y {
-    //                     println!("A collision has occured at {}, {}", t.x, t.y);
-    //                     is_colliding = true;
-    //                     break;
-    //                 }
-    //             }
-    //             // this collision entity doesn't have a position.
-    //             None => (),
-    //         }
-    //     }
-    // }
-
-    if !is_colliding {
-        // its okay to move to new_pos.
-        let pos = positions
-            .get_mut(&entity)
-            .expect(&(format!("an entity didn't have a position? entity id: {}", entity.0)));
-
-        // units can't move more than a distance of 1
-        // todo: what happens if the dt becomes super large?
-        // if this occurs then this distance restriction could be hit and the unit won't move as far
-        // however there are a lot of other issues like collision detection and such.
-        if pos.distance(&new_pos) > 100.0 {
-            return;
-        }
-
-        *pos = new_pos;
+    condition: &Condition,
+    solid_containers: &ComponentManager<SolidContainer>,
+    energy_levels: &ComponentManager<EnergyLevel>,
+) -> bool {
+    match condition {
+        Condition::CargoFull => match solid_containers.get(entity) {
+            Some(t) => t.is_full(),
+            // can't carry anything so it is as full as it gets.
+            None => true,
+        },
+        Condition::CargoEmpty => match solid_containers.get(entity) {
+            Some(t) => t.total() == 0,
+            None => true,
+        },
+        Condition::MineEmpty(mine) => match solid_containers.get(mine) {
+            Some(t) => t.total() == 0,
+            None => true,
+        },
+        Condition::EnergyLow => match energy_levels.get(entity) {
+            Some(t) => t.value <= ENERGY_LOW_THRESHOLD,
+            // units without an energy level don't run out.
+            None => false,
+        },
     }
 }
 
-// hive should be the only building that is non moveable.
-// all other "buildings" are moveable units.
-pub fn game_update(game_state: GameState, dt: f32, game_input: &GameInput) -> GameState {
+/// energy of moving between two tiles, base_cost for every tile crossed times
+/// the terrain cost of the tile entered.
+fn move_energy(tile_map: &TileMap, base_cost: u32, from: (u32, u32), to: (u32, u32)) -> u32 {
+    tile_map
+        .layout()
+        .tile_distance(from.0, from.1, to.0, to.1)
+        .saturating_mul(tile_map.terrain_cost(to.0, to.1))
+        .saturating_mul(base_cost)
+}
+
+/// runs the control flow commands from the program counter on until an action
+/// command is up next. returns false if the unit has nothing to run this tick.
+fn run_control_flow(
+    entity: &Entity,
+    memory: &mut Memory,
+    solid_containers: &ComponentManager<SolidContainer>,
+    energy_levels: &ComponentManager<EnergyLevel>,
+    events: &mut Vec<GameEventKind>,
+) -> bool {
+    // bounded by the program length so jumps that never reach an action
+    // can't hang the update.
+    for _ in 0..=memory.commands.len() {
+        let pc = memory.program_counter as usize;
+        let mut jump_to = |label: &str| match find_label(&memory.commands, label) {
+            Some(t) => Some(t),
+            None => {
+                events.push(GameEventKind::UnknownLabel {
+                    unit: *entity,
+                    label: label.to_string(),
+                });
+                None
+            }
+        };
+        let next_pc = match &memory.commands[pc] {
+            Command::Label(_) => Some(pc + 1),
+            Command::Jump(label) => jump_to(label),
+            Command::JumpIf(condition, label) => {
+                if check_condition(entity, condition, solid_containers, energy_levels) {
+                    jump_to(label)
+                } else {
+                    Some(pc + 1)
+                }
+            }
+            Command::Halt => None,
+            _ => return true,
+        };
+
+        match next_pc {
+            Some(t) => {
+                memory.program_counter = if t >= memory.commands.len() {
+                    0
+                } else {
+                    t as u32
+                };
+            }
+            None => {
+                memory.halted = true;
+                return false;
+            }
+        }
+    }
+    return false;
+}
+
+/// tops up the energy of everything close enough to the hive.
+fn recharge_system(
+    hive_pos: &Position,
+    rate: u32,
+    layout: GridLayout,
+    positions: &ComponentManager<Position>,
+    energy_levels: &mut ComponentManager<EnergyLevel>,
+) {
+    for (_, energy, p) in energy_levels.join_mut(positions) {
+        let near = layout.tile_distance(p.x, p.y, hive_pos.x, hive_pos.y) <= HIVE_RECHARGE_RANGE;
+        if near && rate > 0 && energy.value < energy.max {
+            energy.value = (energy.value + rate).min(energy.max);
+            energy.depleted = false;
+        }
+    }
+}
+
+/// MoveD speed of a unit carrying the container, the heavier the load the slower.
+fn unit_speed(container: Option<&SolidContainer>) -> f32 {
+    let load_share = match container {
+        Some(t) => match t.max_load {
+            Some(max) if max > 0 => (t.load() as f32 / max as f32).min(1.0),
+            _ => 0.0,
+        },
+        None => 0.0,
+    };
+    UNIT_BASE_SPEED * (1.0 - FULL_LOAD_SLOWDOWN * load_share)
+}
+
+/// first colliding entity that entity would run into at new_pos.
+/// entities it already overlaps only block moves that bring it closer,
+/// so a unit can always back out of a pile up.
+fn find_collision(
+    entity: &Entity,
+    positions: &ComponentManager<Position>,
+    collisions: &ComponentManager<Collision>,
+    new_pos: &Position,
+) -> Option<Entity> {
+    let collision = match collisions.get(entity) {
+        Some(t) if t.value => t,
+        // entities without collision go through everything.
+        _ => return None,
+    };
+    let moved = collision.circle_at(new_pos);
+    let current = positions.get(entity).map(|p| collision.circle_at(p));
+
+    for (other, other_collision, other_pos) in collisions.join(positions) {
+        if other == entity || !other_collision.value {
+            continue;
+        }
+        let other_circle = other_collision.circle_at(other_pos);
+        if !collides(&moved, &other_circle) {
+            continue;
+        }
+        let closer = match &current {
+            Some(c) => {
+                moved.center.distance(&other_circle.center)
+                    < c.center.distance(&other_circle.center)
+            }
+            None => true,
+        };
+        if closer {
+            return Some(*other);
+        }
+    }
+    return None;
+}
+
+// not all items that have positions are moveable, should there exist moveable componetns?
+// currently not a good way to tie component X first entity to its other components. ./shrug
+// returns false if the move was refused.
+fn movement_system(
+    entity: &Entity,
+    positions: &mut ComponentManager<Position>,
+    collisions: &ComponentManager<Collision>,
+    tile_map: &TileMap,
+    new_pos: Position,
+) -> bool {
+    // off the map or into a wall.
+    if !tile_map.is_walkable(new_pos.x, new_pos.y) {
+        return false;
+    }
+    if find_collision(entity, positions, collisions, &new_pos).is_some() {
+        return false;
+    }
+
+    // its okay to move to new_pos.
+    let pos = positions
+        .get_mut(&entity)
+        .unwrap_or_else(|| panic!("an entity didn't have a position? entity id: {}", entity.0));
+
+    // units can't move more than 1 tile along either axis, so diagonal steps are allowed.
+    // todo: what happens if the dt becomes super large?
+    // if this occurs then this distance restriction could be hit and the unit won't move as far
+    // however there are a lot of other issues like collision detection and such.
+    let x_dist =
+        (pos.x as f32 * 100.0 + pos.offset.x) - (new_pos.x as f32 * 100.0 + new_pos.offset.x);
+    let y_dist =
+        (pos.y as f32 * 100.0 + pos.offset.y) - (new_pos.y as f32 * 100.0 + new_pos.offset.y);
+    if x_dist.abs() > 100.0 || y_dist.abs() > 100.0 {
+        return false;
+    }
+
+    *pos = new_pos;
+    return true;
+}
+
+fn check_user_command(
+    game_state: &GameState,
+    hive_pos: &Position,
+    command: &UserCommand,
+) -> Result<(), UserCommandError> {
+    let entity = command.entity();
+    if !game_state.is_alive(&entity) {
+        return Err(UserCommandError::UnknownEntity(entity));
+    }
+    let pos = match (
+        game_state.positions.get(&entity),
+        game_state.memory.contains(&entity),
+    ) {
+        (Some(t), true) => t,
+        _ => return Err(UserCommandError::NotProgrammable(entity)),
+    };
+
+    let empty = match command {
+        UserCommand::LoadProgram(_, prog) => prog.is_empty(),
+        UserCommand::LoadScript(_, source) => source.trim().is_empty(),
+        UserCommand::LoadCommand(..) => false,
+    };
+    if empty {
+        return Err(UserCommandError::EmptyProgram(entity));
+    }
+
+    let distance = game_state
+        .tile_map
+        .layout()
+        .tile_distance(pos.x, pos.y, hive_pos.x, hive_pos.y);
+    if distance > HIVE_COMMAND_RANGE {
+        return Err(UserCommandError::OutOfHiveRange {
+            unit: entity,
+            distance: distance,
+        });
+    }
+    return Ok(());
+}
+
+// hive should be the only building that is non moveable.
+// all other "buildings" are moveable units.
+pub fn game_update(game_state: GameState, dt: f32, game_input: &GameInput) -> GameUpdate {
     // this clone is cloning a &GameState and not a GameState?
     let mut new_game_state = game_state.clone();
+    // stamped with the tick once the update is done.
+    let mut events = Vec::<GameEventKind>::new();
 
     // Process player commands(input).
 
@@ -652,191 +1496,322 @@ pub fn game_update(game_state: GameState, dt: f32, game_input: &GameInput) -> Ga
         }
     }
 
-    // todo: entities to load commands must be near the hive.
+    let hive_pos = match new_game_state.hive_entity {
+        Some(hive) => new_game_state.get_entity_position(&hive),
+        None => Position::new(0, 0),
+    };
+
+    let mut command_results = Vec::new();
     for input_command in game_input.user_commands.iter() {
+        if command_results
+            .iter()
+            .any(|r: &Result<(), UserCommandError>| r.is_err())
+        {
+            command_results.push(Err(UserCommandError::Skipped));
+            continue;
+        }
+
+        let result = check_user_command(&new_game_state, &hive_pos, input_command);
+        if let Err(error) = &result {
+            events.push(GameEventKind::ProgramRejected {
+                unit: input_command.entity(),
+                error: error.clone(),
+            });
+            command_results.push(result);
+            continue;
+        }
+
+        // check_user_command made sure the memory exists.
+        let memory = new_game_state
+            .memory
+            .get_mut(&input_command.entity())
+            .unwrap();
         match input_command {
-            UserCommand::LoadProgram(entity_p, prog) => {
-                println!("Loading full program into {}", entity_p.0);
-                match new_game_state.positions.get(&entity_p) {
-                    Some(t) => {
-                        // 0, 0 is hive position
-                        // has a range of 5.
-                        if manhat_distance(t.x, t.y, 0, 0) > 5 {
-                            println!("unable to load commands into entity as its far away");
-                            // todo: need some sort of log listing and reporting to the user.
-                        } else {
-                            match new_game_state.memory.get_mut(&entity_p) {
-                                Some(t) => {
-                                    t.commands.clear();
-                                    let mut new_program = prog.clone();
-                                    t.commands.append(&mut new_program);
-                                    // let new_program = Prog.clone();
-                                    // t.commands.append(&mut (Prog.clone()));
-                                }
-                                None => (),
-                            }
-                        }
-                    }
-                    None => (),
-                }
+            UserCommand::LoadProgram(_, prog) => {
+                memory.reset();
+                memory.commands = prog.clone();
             }
-            UserCommand::LoadCommand(entity_c, command) => {
-                match new_game_state.positions.get(&entity_c) {
-                    Some(t) => {
-                        // 0, 0 is hive position
-                        // has a range of 5.
-                        if manhat_distance(t.x, t.y, 0, 0) > 5 {
-                            println!("unable to load commands into entity as its far away");
-                            // todo: need some sort of log listing and reporting to the user.
-                        } else {
-                            match new_game_state.memory.get_mut(&entity_c) {
-                                Some(t) => {
-                                    t.commands.push(command.clone());
-                                }
-                                None => (),
-                            }
-                        }
-                    }
-                    None => (),
-                };
+            UserCommand::LoadCommand(_, command) => {
+                memory.commands.push(command.clone());
+            }
+            UserCommand::LoadScript(_, source) => {
+                memory.reset();
+                memory.script = Some(source.clone());
+                memory.script_generation = memory.script_generation.wrapping_add(1);
             }
         }
+        command_results.push(result);
     }
 
     if new_game_state.has_hive() {
         if game_input.create_unit {
-            // if entity exists at pos_component (0, 1) then we can't spawn if that entity has collision.
-            let mut is_colliding = false;
-            for tmp_e in new_game_state.entity_manager.entities.iter() {
-                match new_game_state.positions.get(&tmp_e) {
-                    Some(t) => {
-                        if t.x == 0 && t.y == 1 {
-                            is_colliding = true;
-                        } else {
-                            is_colliding = false;
-                        }
+            // can't spawn if the new unit would overlap anything with collision.
+            // a hive walled in by the map has nowhere to put units.
+            match spawn_tile(&new_game_state.tile_map, &hive_pos) {
+                Some(spawn_pos) => {
+                    let spawn_circle = Collision::solid(UNIT_RADIUS).circle_at(&spawn_pos);
+                    let is_colliding = new_game_state.collision.iter().any(|(e, c)| {
+                        c.value
+                            && new_game_state
+                                .positions
+                                .get(e)
+                                .map_or(false, |p| collides(&spawn_circle, &c.circle_at(p)))
+                    });
+
+                    if is_colliding {
+                        events.push(GameEventKind::SpawnBlocked {
+                            position: spawn_pos,
+                        });
+                    } else {
+                        let unit = spawn_unit(&mut new_game_state, spawn_pos);
+                        events.push(GameEventKind::UnitSpawned { unit: unit });
                     }
-                    None => is_colliding = false,
                 }
-            }
-
-            if is_colliding {
-                println!("Cannot create new entity at same position as another");
-            } else {
-                spawn_unit(&mut new_game_state, Position::new(0, 1));
+                None => events.push(GameEventKind::SpawnBlocked {
+                    position: hive_pos.clone(),
+                }),
             }
         }
     }
 
-    for e in new_game_state.entity_manager.entities.iter() {
+    if new_game_state.has_hive() {
+        recharge_system(
+            &hive_pos,
+            new_game_state.energy.recharge,
+            new_game_state.tile_map.layout(),
+            &new_game_state.positions,
+            &mut new_game_state.energy_levels,
+        );
+    }
+
+    // entities run in id order. moves see the positions of entities that already moved this
+    // tick, so when two units head for the same spot the one with the lower id gets there and
+    // the other one is blocked.
+    let mut order: Vec<Entity> = new_game_state
+        .entity_manager
+        .entities
+        .iter()
+        .cloned()
+        .collect();
+    order.sort();
+    for e in order.iter() {
         match new_game_state.memory.get_mut(&e) {
-            Some(mut memory_comp) => {
+            Some(memory_comp) => {
                 // process memory.
-                if memory_comp.commands.len() > 0 {
+                if memory_comp.commands.len() > 0
+                    && !memory_comp.halted
+                    && run_control_flow(
+                        &e,
+                        memory_comp,
+                        &new_game_state.solid_containers,
+                        &new_game_state.energy_levels,
+                        &mut events,
+                    )
+                {
                     let current_command =
                         &memory_comp.commands[memory_comp.program_counter as usize];
+
+                    let tile_before = new_game_state.positions.get(&e).map(|p| (p.x, p.y));
+                    // where a move command steps to this tick.
+                    let move_step = match current_command {
+                        Command::MoveP(position) => Some(position.clone()),
+                        Command::MoveD(destination) => {
+                            // speed is in hundredths of a tile per second.
+                            let speed = unit_speed(new_game_state.solid_containers.get(&e));
+                            new_game_state.positions.get(&e).map(|current| {
+                                let step = speed as f64 * dt as f64 / 100.0;
+                                Position::from_world_clamped(
+                                    current
+                                        .to_world()
+                                        .move_towards(&destination.to_world(), step),
+                                )
+                            })
+                        }
+                        _ => None,
+                    };
+                    let base_cost = new_game_state.energy.cost(current_command);
+                    // a move has to be paid for in full before the unit sets off.
+                    let required = match (tile_before, &move_step) {
+                        (Some(a), Some(p)) => base_cost.max(move_energy(
+                            &new_game_state.tile_map,
+                            base_cost,
+                            a,
+                            (p.x, p.y),
+                        )),
+                        _ => base_cost,
+                    };
+
+                    // entities without an energy level don't need any.
+                    if let Some(energy) = new_game_state.energy_levels.get_mut(&e) {
+                        if energy.value < required {
+                            // the unit stays on the command until recharged.
+                            if !energy.depleted {
+                                energy.depleted = true;
+                                events.push(GameEventKind::OutOfEnergy { unit: *e });
+                            }
+                            continue;
+                        }
+                    }
+                    let mut spent = 0;
+
                     let mut move_pc = true;
                     match current_command {
                         Command::MoveP(position) => {
-                            if new_game_state.positions.get(&e).is_some() {
-                                movement_system(
+                            if new_game_state.positions.get(&e).is_some()
+                                && !movement_system(
                                     &e,
                                     &mut new_game_state.positions,
-                                    &mut new_game_state.collision,
+                                    &new_game_state.collision,
+                                    &new_game_state.tile_map,
                                     position.clone(),
-                                );
+                                )
+                            {
+                                events.push(GameEventKind::MoveBlocked {
+                                    unit: *e,
+                                    position: position.clone(),
+                                });
                             }
                         }
                         Command::MoveD(destination) => {
-                            // println!("Move D: {},{}", destination.get_x(), destination.get_y());
-                            let new_x;
-                            let new_y;
-                            let mut new_offset_x;
-                            let mut new_offset_y;
-                            {
-                                let tmp_p = new_game_state.positions.get(&e).unwrap();
-                                // current position
-
-                                let speed = 10.0; // meter per second
-                                new_x = tmp_p.x;
-                                new_y = tmp_p.y;
-                                new_offset_x = tmp_p.offset.x;
-                                new_offset_y = tmp_p.offset.y;
-
-                                // 1 is the speed 1 meter per second.
-
-                                let x_dist = uclid_distance(
-                                    tmp_p.x as f32 * 100.0 + tmp_p.offset.x,
-                                    0.0,
-                                    destination.x as f32 * 100.0 + destination.offset.x,
-                                    0.0,
-                                );
-                                let y_dist = uclid_distance(
-                                    0.0,
-                                    tmp_p.y as f32 * 100.0 + tmp_p.offset.y,
-                                    0.0,
-                                    destination.y as f32 * 100.0 + destination.offset.y,
-                                );
-
-                                if tmp_p.x >= destination.x && x_dist > 2.5 {
-                                    new_offset_x -= speed * dt;
-                                } else if tmp_p.x <= destination.x && x_dist > 2.5 {
-                                    new_offset_x += speed * dt;
-                                }
-
-                                if tmp_p.y >= destination.y && y_dist > 2.5 {
-                                    new_offset_y -= speed * dt;
-                                } else if tmp_p.y <= destination.y && y_dist > 2.5 {
-                                    new_offset_y += speed * dt;
-                                }
-                            }
+                            let new_pos = move_step.clone().unwrap();
 
-                            let new_pos =
-                                Position::new_with_offset(new_x, new_y, new_offset_x, new_offset_y);
-
-                            movement_system(
+                            if movement_system(
                                 &e,
                                 &mut new_game_state.positions,
-                                &mut new_game_state.collision,
+                                &new_game_state.collision,
+                                &new_game_state.tile_map,
                                 new_pos.clone(),
-                            );
-
-                            if new_pos.distance(&destination) > 5.0 {
-                                move_pc = false;
+                            ) {
+                                if new_pos.distance(&destination) > 5.0 {
+                                    move_pc = false;
+                                }
+                            } else {
+                                events.push(GameEventKind::MoveBlocked {
+                                    unit: *e,
+                                    position: new_pos.clone(),
+                                });
+                                // a destination taken by another entity or off the walkable
+                                // map can't be reached, the unit stops where it is. otherwise it
+                                // waits for the way to clear.
+                                move_pc = !new_game_state
+                                    .tile_map
+                                    .is_walkable(destination.x, destination.y)
+                                    || find_collision(
+                                        &e,
+                                        &new_game_state.positions,
+                                        &new_game_state.collision,
+                                        destination,
+                                    )
+                                    .is_some();
                             }
                         }
-                        Command::Harvest(minable_entity) => {
-                            if new_game_state.positions.get(&e).is_some() {
-                                harvest_system(
-                                    &e,
-                                    &mut new_game_state.positions,
-                                    &mut new_game_state.solid_containers,
-                                    &minable_entity,
-                                    "iron",
-                                );
+                        Command::Harvest(minable_entity, resource) => {
+                            match harvest_system(
+                                &e,
+                                &mut new_game_state.positions,
+                                &mut new_game_state.solid_containers,
+                                &minable_entity,
+                                *resource,
+                                new_game_state.tile_map.layout(),
+                            ) {
+                                Ok(amount) => {
+                                    spent = new_game_state.energy.harvest;
+                                    events.push(GameEventKind::Harvested {
+                                        unit: *e,
+                                        node: *minable_entity,
+                                        resource: *resource,
+                                        amount: amount,
+                                    });
+                                    let left = new_game_state.solid_containers.get(minable_entity);
+                                    if new_game_state.iron_mines.contains(minable_entity)
+                                        && left.map_or(false, |t| t.total() == 0)
+                                    {
+                                        events.push(GameEventKind::MineDepleted {
+                                            node: *minable_entity,
+                                        });
+                                    }
+                                }
+                                Err(reason) => events.push(GameEventKind::HarvestFailed {
+                                    unit: *e,
+                                    node: *minable_entity,
+                                    reason: reason,
+                                }),
                             }
                         }
                         Command::Deposit(mineable_entity) => {
-                            if new_game_state.positions.get(&mineable_entity).is_some() {
-                                harvest_system(
-                                    &mineable_entity,
-                                    &mut new_game_state.positions,
-                                    &mut new_game_state.solid_containers,
-                                    &e,
-                                    "iron",
-                                );
+                            // a deposit is a harvest from the unit's point of view of the target,
+                            // whatever the unit holds goes first.
+                            let resource = new_game_state
+                                .solid_containers
+                                .get(&e)
+                                .and_then(|t| t.first_held())
+                                .unwrap_or(Resource::Iron);
+                            match harvest_system(
+                                &mineable_entity,
+                                &mut new_game_state.positions,
+                                &mut new_game_state.solid_containers,
+                                &e,
+                                resource,
+                                new_game_state.tile_map.layout(),
+                            ) {
+                                Ok(amount) => {
+                                    spent = new_game_state.energy.deposit;
+                                    events.push(GameEventKind::Deposited {
+                                        unit: *e,
+                                        target: *mineable_entity,
+                                        resource: resource,
+                                        amount: amount,
+                                    })
+                                }
+                                Err(reason) => events.push(GameEventKind::DepositFailed {
+                                    unit: *e,
+                                    target: *mineable_entity,
+                                    reason: reason,
+                                }),
                             }
                         }
-                        #[allow(unreachable_patterns)]
-                        _ => {
-                            todo!("Unhandled command")
+                        Command::Wait(ticks) => {
+                            let remaining = memory_comp.wait_remaining.unwrap_or(*ticks);
+                            if remaining > 1 {
+                                memory_comp.wait_remaining = Some(remaining - 1);
+                                move_pc = false;
+                            } else {
+                                memory_comp.wait_remaining = None;
+                            }
                         }
+                        // already handled by run_control_flow.
+                        Command::Label(_)
+                        | Command::Jump(_)
+                        | Command::JumpIf(..)
+                        | Command::Halt => {}
+                    }
+
+                    // moves pay for every tile entered, more on rough terrain.
+                    let tile_after = new_game_state.positions.get(&e).map(|p| (p.x, p.y));
+                    if let (Some(a), Some(b)) = (tile_before, tile_after) {
+                        spent = spent.saturating_add(move_energy(
+                            &new_game_state.tile_map,
+                            base_cost,
+                            a,
+                            b,
+                        ));
+                    }
+                    if let Some(energy) = new_game_state.energy_levels.get_mut(&e) {
+                        energy.value = energy.value.saturating_sub(spent);
                     }
 
                     if move_pc {
-                        memory_comp.program_counter += 1;
-                        if (memory_comp.program_counter as usize) >= memory_comp.commands.len() {
+                        if memory_comp.script.is_some() {
+                            // scripts hand out one command at a time, drop the finished
+                            // one so the script is asked for the next.
+                            memory_comp.commands.remove(0);
                             memory_comp.program_counter = 0;
+                        } else {
+                            memory_comp.program_counter += 1;
+                            if (memory_comp.program_counter as usize) >= memory_comp.commands.len()
+                            {
+                                memory_comp.program_counter = 0;
+                            }
                         }
                     }
                 }
@@ -845,35 +1820,62 @@ pub fn game_update(game_state: GameState, dt: f32, game_input: &GameInput) -> Ga
         }
     }
 
-    return new_game_state;
+    let tick = new_game_state.tick;
+    new_game_state.tick += 1;
+    return GameUpdate {
+        state: new_game_state,
+        command_results: command_results,
+        events: events
+            .into_iter()
+            .map(|kind| GameEvent {
+                tick: tick,
+                kind: kind,
+            })
+            .collect(),
+    };
 }
 
 // likely can be moved to another file.
-// #[cfg(feature = "gui")]
+#[cfg(feature = "gui")]
 pub fn game_sdl2_render(game_state: &GameState, canvas: &mut Canvas<Window>) -> () {
-    canvas.set_draw_color(Color::RGB(0, 255, 0));
-
     // draw grid.
     // display aspect.
     let pixels_per_meter: u16 = 50;
 
-    // todo: game state should have a world bounds.
-    for x_pos in 0..20 {
-        for y_pos in 0..20 {
+    let tile_map = game_state.get_tile_map();
+    let bounds = tile_map.bounds();
+    let layout = bounds.layout;
+    for x_pos in 0..bounds.width {
+        for y_pos in 0..bounds.height {
+            canvas.set_draw_color(tile_color(tile_map.get(x_pos, y_pos).unwrap()));
             // fill rect operates in visible pixel space.
             // todo: have function for translate between pixel space -> world space and vise versa.
-            let vis_tile_pos = world_to_display(&Position::new(x_pos, y_pos), pixels_per_meter);
+            let vis_tile_pos =
+                layout_to_display(&Position::new(x_pos, y_pos), layout, pixels_per_meter);
             // let tile_width = Position::new(tile_width_meters, tile_width_meters);
             // let tile_draw = world_to_display(&tile_pos, pixels_per_meter);
             // let tile_draw_w = world_to_display(&tile_width, pixels_per_meter);
 
-            let _p = canvas.fill_rect(Rect::new(
-                vis_tile_pos.0,
-                vis_tile_pos.1,
-                // allows for a margin to be created if less than pixel_tile_width /
-                (pixels_per_meter - 5) as u32,
-                (pixels_per_meter - 5) as u32,
-            ));
+            match layout {
+                GridLayout::Square => {
+                    let _p = canvas.fill_rect(Rect::new(
+                        vis_tile_pos.0,
+                        vis_tile_pos.1,
+                        // allows for a margin to be created if less than pixel_tile_width /
+                        (pixels_per_meter - 5) as u32,
+                        (pixels_per_meter - 5) as u32,
+                    ));
+                }
+                GridLayout::Hex => {
+                    let half = (pixels_per_meter / 2) as i32;
+                    fill_hex(
+                        canvas,
+                        (vis_tile_pos.0 + half, vis_tile_pos.1 + half),
+                        // same margin as the square tiles.
+                        (pixels_per_meter - 5) as f32 / 3f32.sqrt(),
+                    );
+                }
+            }
         }
     }
 
@@ -882,32 +1884,62 @@ pub fn game_sdl2_render(game_state: &GameState, canvas: &mut Canvas<Window>) ->
     let texture_creator: TextureCreator<_> = canvas.texture_creator();
 
     // draw units ontop of grid.
-    for entity in game_state.entity_manager.entities.iter() {
-        match game_state.positions.get(&entity) {
-            Some(pos) => {
-                // where to draw.
-                let vis_pos = world_to_display(pos, pixels_per_meter);
-
-                let circle_texture = create_circle_texture(canvas, &texture_creator, 10).unwrap();
-
-                canvas
-                    .copy(
-                        &circle_texture,
-                        None,
-                        Rect::new(vis_pos.0 as i32, vis_pos.1 as i32, 11, 11),
-                    )
-                    .unwrap();
-                // let _p = canvas.fill_rect(Rect::new(vis_pos.0 as i32, vis_pos.1 as i32, 10, 10));
-                // how to determine what to draw?
-            }
-            None => (),
-        }
+    for (_, pos) in game_state.positions.iter() {
+        // where to draw.
+        let vis_pos = layout_to_display(pos, layout, pixels_per_meter);
+
+        let circle_texture = create_circle_texture(canvas, &texture_creator, 10).unwrap();
+
+        canvas
+            .copy(
+                &circle_texture,
+                None,
+                Rect::new(vis_pos.0 as i32, vis_pos.1 as i32, 11, 11),
+            )
+            .unwrap();
+        // let _p = canvas.fill_rect(Rect::new(vis_pos.0 as i32, vis_pos.1 as i32, 10, 10));
+        // how to determine what to draw?
+    }
+}
+
+#[cfg(feature = "gui")]
+fn tile_color(tile: &Tile) -> Color {
+    if !tile.walkable {
+        return Color::RGB(90, 90, 90);
+    }
+    match tile.deposit {
+        Some(Resource::Iron) => Color::RGB(150, 160, 180),
+        Some(Resource::Copper) => Color::RGB(200, 120, 50),
+        // rougher terrain is a darker green.
+        None => Color::RGB(0, (255 / tile.terrain_cost.max(1)).max(80) as u8, 0),
+    }
+}
+
+/// pointy topped hexagon around center, size is the distance from the center to a corner.
+/// filled one pixel row at a time as the canvas has no polygon fill.
+#[cfg(feature = "gui")]
+fn fill_hex(canvas: &mut Canvas<Window>, center: (i32, i32), size: f32) {
+    let half_width = size * 3f32.sqrt() / 2.0;
+    let rows = size as i32;
+    for dy in -rows..=rows {
+        // the sides are straight for the middle half, the top and bottom come to a point.
+        let from_tip = size - (dy as f32).abs();
+        let width = if from_tip >= size / 2.0 {
+            half_width
+        } else {
+            half_width * from_tip / (size / 2.0)
+        } as i32;
+        let _p = canvas.draw_line(
+            Point::new(center.0 - width, center.1 + dy),
+            Point::new(center.0 + width, center.1 + dy),
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn components() {
@@ -916,7 +1948,7 @@ mod tests {
         let new_e = p.create();
         assert_eq!(new_e.0, 1);
 
-        assert_eq!(position_component_manager.contains(&new_e), false);
+        assert!(!position_component_manager.contains(&new_e));
     }
 
     #[test]
@@ -931,14 +1963,14 @@ mod tests {
             pos.x = 10;
             pos.y = 20;
         }
-        assert_eq!(position_component_manager.contains(&new_e), true);
+        assert!(position_component_manager.contains(&new_e));
 
         match position_component_manager.lookup.get(&new_e) {
             Some(&t) => assert_eq!(t, 0 as usize),
             None => assert_eq!(true, false),
         };
 
-        assert_eq!(position_component_manager.contains(&new_e), true);
+        assert!(position_component_manager.contains(&new_e));
 
         let pos = match position_component_manager.get(&new_e) {
             Some(t) => t,
@@ -961,17 +1993,158 @@ mod tests {
         }
 
         position_component_manager.remove(&new_e);
-        assert_eq!(position_component_manager.contains(&new_e), false);
+        assert!(!position_component_manager.contains(&new_e));
         assert_eq!(position_component_manager.components.len(), 0);
     }
 
+    #[test]
+    fn component_create_with_replaces() {
+        let mut positions = ComponentManager::<Position>::new();
+        let e = Entity(1);
+
+        *positions.create_with(&e, Position::new(1, 2)) = Position::new(3, 4);
+        assert_eq!(positions.get(&e), Some(&Position::new(3, 4)));
+
+        positions.create_with(&e, Position::new(5, 6));
+        assert_eq!(positions.get(&e), Some(&Position::new(5, 6)));
+        positions.create(&e);
+        assert_eq!(positions.get(&e), Some(&Position::default()));
+        assert_eq!(positions.iter().count(), 1);
+    }
+
+    #[test]
+    fn component_pair_mut() {
+        let mut containers = ComponentManager::<SolidContainer>::new();
+        let (a, b, c) = (Entity(1), Entity(2), Entity(3));
+        containers.create(&a).set(Resource::Iron, 5);
+        containers.create(&b);
+
+        for (from, to) in [(a, b), (b, a)].iter() {
+            let (from_c, to_c) = containers.get_pair_mut(from, to).unwrap();
+            let taken = from_c.take(Resource::Iron, 5);
+            to_c.add(Resource::Iron, taken);
+        }
+        assert_eq!(containers.get(&a).unwrap().get(Resource::Iron), 5);
+        assert_eq!(containers.get(&b).unwrap().get(Resource::Iron), 0);
+
+        assert!(containers.get_pair_mut(&a, &a).is_none());
+        assert!(containers.get_pair_mut(&a, &c).is_none());
+    }
+
+    #[test]
+    fn component_joins() {
+        let mut positions = ComponentManager::<Position>::new();
+        let mut memory = ComponentManager::<Memory>::new();
+        let mut containers = ComponentManager::<SolidContainer>::new();
+        for i in 1..=4 {
+            positions.create_with(&Entity(i), Position::new(i as u32, 0));
+        }
+        // 2 and 3 are units, 4 only has a container.
+        for i in 2..=3 {
+            memory.create(&Entity(i));
+            containers.create(&Entity(i));
+        }
+        containers.create(&Entity(4)).set(Resource::Iron, 1);
+        memory.create(&Entity(5));
+
+        let joined: Vec<Entity> = positions.join(&memory).map(|(e, _, _)| *e).collect();
+        assert_eq!(joined, vec![Entity(2), Entity(3)]);
+
+        let units: Vec<(Entity, u32)> = positions
+            .join3(&memory, &containers)
+            .map(|(e, p, _, _)| (*e, p.get_x()))
+            .collect();
+        assert_eq!(units, vec![(Entity(2), 2), (Entity(3), 3)]);
+
+        for (_, p, c) in positions.join_mut(&containers) {
+            p.y = c.total();
+        }
+        assert_eq!(positions.get(&Entity(4)), Some(&Position::new(4, 1)));
+        assert_eq!(positions.get(&Entity(2)), Some(&Position::new(2, 0)));
+    }
+
+    #[test]
+    fn component_remove_keeps_lookup() {
+        let mut positions = ComponentManager::<Position>::new();
+        let mut p = EntityManager::new();
+        let entities: Vec<Entity> = (0..4).map(|_| p.create()).collect();
+        for (i, e) in entities.iter().enumerate() {
+            *positions.create(e) = Position::new(i as u32, 0);
+        }
+
+        // first, last and a middle one, every removal moves another component.
+        assert!(positions.remove(&entities[0]));
+        assert!(!positions.remove(&entities[0]));
+        assert!(positions.remove(&entities[3]));
+        assert_eq!(positions.get(&entities[1]), Some(&Position::new(1, 0)));
+        assert_eq!(positions.get(&entities[2]), Some(&Position::new(2, 0)));
+        assert!(positions.remove(&entities[1]));
+
+        assert_eq!(positions.get(&entities[0]), None);
+        assert_eq!(positions.get(&entities[1]), None);
+        assert_eq!(positions.get(&entities[2]), Some(&Position::new(2, 0)));
+        assert_eq!(positions.get(&entities[3]), None);
+        let left: Vec<(&Entity, &Position)> = positions.iter().collect();
+        assert_eq!(left, vec![(&entities[2], &Position::new(2, 0))]);
+
+        // the freed slot can be used again.
+        *positions.create(&entities[0]) = Position::new(7, 7);
+        assert_eq!(positions.get(&entities[0]), Some(&Position::new(7, 7)));
+        assert_eq!(positions.get(&entities[2]), Some(&Position::new(2, 0)));
+    }
+
+    #[test]
+    fn destroy_entity_removes_all_components() {
+        let (mut game_state, mine, unit) = program_state(10, vec![]);
+        let hive = game_state.get_hive().unwrap();
+        game_state.iron_mines.create(&mine);
+        *game_state.collision.create(&mine) = Collision::solid(NODE_RADIUS);
+
+        assert!(game_state.destroy_entity(&mine));
+        assert!(!game_state.destroy_entity(&mine));
+        assert!(!game_state.is_alive(&mine));
+        assert!(!game_state.positions.contains(&mine));
+        assert!(!game_state.collision.contains(&mine));
+        assert!(!game_state.iron_mines.contains(&mine));
+        assert!(!game_state.solid_containers.contains(&mine));
+
+        // the other entities are untouched.
+        assert_eq!(game_state.get_entity_position(&unit), Position::new(0, 1));
+        assert_eq!(game_state.get_entity_position(&hive), Position::new(0, 0));
+        assert!(game_state.memory.contains(&unit));
+
+        assert!(game_state.destroy_entity(&unit));
+        assert_eq!(game_state.get_programable_units().len(), 0);
+        assert!(game_state.destroy_entity(&hive));
+        assert!(!game_state.has_hive());
+        assert_eq!(game_state.entity_manager.count(), 0);
+    }
+
+    #[test]
+    fn stale_entity_is_rejected() {
+        let (mut game_state, _, unit) = program_state(10, vec![]);
+        game_state.destroy_entity(&unit);
+        let reused = game_state.entity_manager.create();
+        assert_eq!(reused.index(), unit.index());
+
+        let mut game_input = GameInput::default();
+        game_input
+            .user_commands
+            .push(UserCommand::LoadProgram(unit, vec![Command::Halt]));
+        let update = game_update(game_state, 0.1, &game_input);
+        assert_eq!(
+            update.command_results,
+            vec![Err(UserCommandError::UnknownEntity(unit))]
+        );
+    }
+
     #[test]
     fn spawn_unit() {
         let mut game_state = game_init();
         let mut game_input = GameInput::default();
         game_input.create_unit = true;
 
-        game_state = game_update(game_state, 0.1, &game_input);
+        game_state = game_update(game_state, 0.1, &game_input).state;
 
         // can't create entities if hive isn't a thing
         assert_eq!(game_state.entity_manager.count(), 0);
@@ -995,7 +2168,7 @@ mod tests {
         }
         {
             let mut unit_s = solid_c.create(&unit);
-            unit_s.iron_count = 0;
+            unit_s.set(Resource::Iron, 0);
         }
 
         let iron_node = entity_manager.create();
@@ -1006,16 +2179,26 @@ mod tests {
         }
         {
             let mut iron_s = solid_c.create(&iron_node);
-            iron_s.iron_count = 100;
+            iron_s.set(Resource::Iron, 100);
         }
 
-        harvest_system(&unit, &mut pos_c, &mut solid_c, &iron_node, "iron");
+        assert_eq!(
+            harvest_system(
+                &unit,
+                &mut pos_c,
+                &mut solid_c,
+                &iron_node,
+                Resource::Iron,
+                GridLayout::Square
+            ),
+            Ok(1)
+        );
 
         let iron_s = solid_c.get(&iron_node).unwrap();
-        assert_eq!(iron_s.iron_count, 99);
+        assert_eq!(iron_s.get(Resource::Iron), 99);
 
         let unit_s = solid_c.get(&unit).unwrap();
-        assert_eq!(unit_s.iron_count, 1);
+        assert_eq!(unit_s.get(Resource::Iron), 1);
     }
 
     #[test]
@@ -1048,7 +2231,13 @@ mod tests {
             iron_s.value = true;
         }
 
-        movement_system(&unit, &mut pos_c, &mut solid_c, Position::new(0, 1));
+        movement_system(
+            &unit,
+            &mut pos_c,
+            &mut solid_c,
+            &TileMap::new(WorldBounds::default()),
+            Position::new(0, 1),
+        );
 
         let iron_s = pos_c.get(&iron_node).unwrap();
         assert_eq!(*iron_s, Position::new(0, 1));
@@ -1179,4 +2368,967 @@ mod tests {
         let p4 = Position::new_with_offset(1, 2, 50.0, 0.0);
         assert_eq!(p1.distance(&p4), 50.0);
     }
+
+    #[test]
+    fn test_position_sub_and_scale() {
+        let p1 = Position::new_with_offset(3, 2, 20.0, 50.0);
+        let p2 = Position::new_with_offset(1, 1, 50.0, 0.0);
+        assert_eq!(p1.sub(&p2), Position::new_with_offset(1, 1, 70.0, 50.0));
+        // can't go below tile 0.
+        assert_eq!(p2.sub(&p1), Position::new(0, 0));
+        assert_eq!(p2.scale(2.0), Position::new(3, 2));
+
+        let diff = p2.to_world() - p1.to_world();
+        assert!(close(&diff, &WorldPos::new(-1.7, -1.5)), "{:?}", diff);
+        assert_eq!(diff.tile(), (-2, -2));
+        assert_eq!(Position::from_world(diff), None);
+        assert_eq!(Position::from_world_clamped(diff), Position::new(0, 0));
+    }
+
+    // a few thousandths of a tile, f32 offsets round.
+    fn close(a: &WorldPos, b: &WorldPos) -> bool {
+        (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3
+    }
+
+    fn valid_offset(p: &Position) -> bool {
+        p.offset.x >= 0.0 && p.offset.x < 100.0 && p.offset.y >= 0.0 && p.offset.y < 100.0
+    }
+
+    proptest! {
+        #[test]
+        fn prop_carry_keeps_offsets_in_range(
+            x in 0u32..100_000,
+            y in 0u32..100_000,
+            x_off in -1e6f32..1e6,
+            y_off in -1e6f32..1e6,
+        ) {
+            let p = Position::new_with_offset(x, y, x_off, y_off);
+            prop_assert!(valid_offset(&p), "{:?}", p);
+
+            let expected = WorldPos::new(
+                (x as f64 + x_off as f64 / 100.0).max(0.0),
+                (y as f64 + y_off as f64 / 100.0).max(0.0),
+            );
+            // offsets that go below tile 0 stop at 0, 0.
+            if expected.x > 0.0 && expected.y > 0.0 {
+                prop_assert!(close(&p.to_world(), &expected), "{:?} {:?}", p, expected);
+            }
+        }
+
+        #[test]
+        fn prop_world_round_trip(x in 0.0f64..100_000.0, y in 0.0f64..100_000.0) {
+            let p = Position::from_world(WorldPos::new(x, y)).unwrap();
+            prop_assert!(valid_offset(&p), "{:?}", p);
+            prop_assert!(close(&p.to_world(), &WorldPos::new(x, y)));
+            prop_assert_eq!(Position::from_world(p.to_world()), Some(p));
+        }
+
+        #[test]
+        fn prop_add_then_sub(
+            ax in 0u32..10_000, ay in 0u32..10_000, ax_off in 0f32..100.0, ay_off in 0f32..100.0,
+            bx in 0u32..10_000, by in 0u32..10_000, bx_off in 0f32..100.0, by_off in 0f32..100.0,
+        ) {
+            let a = Position::new_with_offset(ax, ay, ax_off, ay_off);
+            let b = Position::new_with_offset(bx, by, bx_off, by_off);
+            let sum = a.add(&b);
+            prop_assert!(valid_offset(&sum), "{:?}", sum);
+            prop_assert_eq!(sum.clone(), b.add(&a));
+            prop_assert!(close(&sum.to_world(), &(a.to_world() + b.to_world())));
+            prop_assert!(close(&sum.sub(&b).to_world(), &a.to_world()));
+            prop_assert!(close(&a.scale(2.0).to_world(), &a.add(&a).to_world()));
+        }
+
+        #[test]
+        fn prop_world_pos_arithmetic(
+            ax in -1e5f64..1e5, ay in -1e5f64..1e5, bx in -1e5f64..1e5, by in -1e5f64..1e5,
+            factor in -10f64..10.0,
+        ) {
+            let a = WorldPos::new(ax, ay);
+            let b = WorldPos::new(bx, by);
+            prop_assert!(close(&(a + b - b), &a));
+            prop_assert!(close(&(a - b), &(a + -b)));
+            prop_assert!(close(&((a + b) * factor), &(a * factor + b * factor)));
+            let (tx, ty) = a.tile();
+            prop_assert!(tx as f64 <= ax && ax < tx as f64 + 1.0);
+            prop_assert!(ty as f64 <= ay && ay < ty as f64 + 1.0);
+        }
+    }
+
+    /// hive at 0,0, a mine at 1,1 with mine_amount iron and a unit at 0,1 running program.
+    fn program_state(mine_amount: u32, program: Vec<Command>) -> (GameState, Entity, Entity) {
+        let mut game_state = GameState::new();
+        game_state.create_hive(0, 0);
+
+        let mine = game_state.entity_manager.create();
+        *game_state.positions.create(&mine) = Position::new(1, 1);
+        game_state
+            .solid_containers
+            .create(&mine)
+            .set(Resource::Iron, mine_amount);
+
+        let unit = super::spawn_unit(&mut game_state, Position::new(0, 1));
+        game_state.memory.get_mut(&unit).unwrap().commands = program;
+        (game_state, mine, unit)
+    }
+
+    fn run_ticks(mut game_state: GameState, ticks: u32) -> GameState {
+        for _ in 0..ticks {
+            game_state = game_update(game_state, 0.1, &GameInput::default()).state;
+        }
+        game_state
+    }
+
+    #[test]
+    fn test_label_and_jump() {
+        let (game_state, mine, unit) = program_state(
+            100,
+            vec![
+                Command::Label("top".to_string()),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Jump("top".to_string()),
+            ],
+        );
+        // labels and jumps don't take a tick, every tick is a harvest.
+        let game_state = run_ticks(game_state, 3);
+        assert_eq!(game_state.get_mineable_count(&mine), Some(97));
+        assert_eq!(game_state.get_mineable_count(&unit), Some(3));
+    }
+
+    #[test]
+    fn test_jump_if_cargo_full() {
+        let (game_state, _, unit) = program_state(
+            100,
+            vec![
+                Command::Label("top".to_string()),
+                Command::JumpIf(Condition::CargoFull, "done".to_string()),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Jump("top".to_string()),
+                Command::Label("done".to_string()),
+                Command::Halt,
+            ],
+        );
+        let game_state = run_ticks(game_state, UNIT_CARGO_CAPACITY + 5);
+        assert_eq!(
+            game_state.get_mineable_count(&unit),
+            Some(UNIT_CARGO_CAPACITY)
+        );
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_jump_if_cargo_empty() {
+        let (game_state, mine, unit) = program_state(
+            100,
+            vec![
+                Command::JumpIf(Condition::CargoEmpty, "skip".to_string()),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Label("skip".to_string()),
+                Command::Halt,
+            ],
+        );
+        let game_state = run_ticks(game_state, 3);
+        assert_eq!(game_state.get_mineable_count(&mine), Some(100));
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_jump_if_mine_empty() {
+        let (game_state, mine, unit) = program_state(
+            2,
+            vec![
+                Command::Label("top".to_string()),
+                Command::JumpIf(Condition::MineEmpty(Entity(2)), "done".to_string()),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Jump("top".to_string()),
+                Command::Label("done".to_string()),
+                Command::Halt,
+            ],
+        );
+        let game_state = run_ticks(game_state, 5);
+        assert_eq!(game_state.get_mineable_count(&mine), Some(0));
+        assert_eq!(game_state.get_mineable_count(&unit), Some(2));
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_jump_if_energy_low() {
+        let program = vec![
+            Command::JumpIf(Condition::EnergyLow, "rest".to_string()),
+            Command::Harvest(Entity(2), Resource::Iron),
+            Command::Halt,
+            Command::Label("rest".to_string()),
+            Command::Halt,
+        ];
+
+        // no energy level, never low.
+        let (mut game_state, _, unit) = program_state(100, program.clone());
+        game_state.energy_levels.remove(&unit);
+        let game_state = run_ticks(game_state, 2);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(1));
+
+        let (mut game_state, _, unit) = program_state(100, program);
+        game_state.energy_levels.get_mut(&unit).unwrap().value = ENERGY_LOW_THRESHOLD;
+        game_state.energy.recharge = 0;
+        let game_state = run_ticks(game_state, 2);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+        assert_eq!(game_state.memory.get(&unit).unwrap().program_counter, 4);
+    }
+
+    #[test]
+    fn test_wait() {
+        let (game_state, _, unit) = program_state(
+            100,
+            vec![
+                Command::Wait(3),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Halt,
+            ],
+        );
+        let game_state = run_ticks(game_state, 3);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+        let game_state = run_ticks(game_state, 1);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(1));
+    }
+
+    #[test]
+    fn test_halt() {
+        let (game_state, _, unit) = program_state(
+            100,
+            vec![Command::Halt, Command::Harvest(Entity(2), Resource::Iron)],
+        );
+        let game_state = run_ticks(game_state, 3);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_load_program_clears_halt() {
+        let (game_state, _, unit) = program_state(100, vec![Command::Halt]);
+        let game_state = run_ticks(game_state, 1);
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+
+        let mut game_input = GameInput::default();
+        game_input.user_commands.push(UserCommand::LoadProgram(
+            unit,
+            vec![Command::Harvest(Entity(2), Resource::Iron)],
+        ));
+        let game_state = game_update(game_state, 0.1, &game_input).state;
+        assert!(!game_state.memory.get(&unit).unwrap().halted);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(1));
+    }
+
+    #[test]
+    fn test_jump_loop_without_action() {
+        let (game_state, _, unit) = program_state(
+            100,
+            vec![
+                Command::Label("spin".to_string()),
+                Command::Jump("spin".to_string()),
+            ],
+        );
+        // must not hang.
+        let game_state = run_ticks(game_state, 2);
+        assert!(!game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_jump_unknown_label_halts() {
+        let (game_state, _, unit) = program_state(
+            100,
+            vec![
+                Command::Jump("nowhere".to_string()),
+                Command::Harvest(Entity(2), Resource::Iron),
+            ],
+        );
+        let game_state = run_ticks(game_state, 2);
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+    }
+
+    fn event_kinds(update: &GameUpdate) -> Vec<GameEventKind> {
+        update.events.iter().map(|e| e.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_harvest_events() {
+        let (mut game_state, mine, unit) = program_state(
+            2,
+            vec![
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Deposit(Entity(1)),
+            ],
+        );
+        *game_state.iron_mines.create(&mine) = MineableNode {
+            current_amount: 2,
+            initial_amount: 2,
+        };
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(update.events[0].tick, 0);
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::Harvested {
+                unit: unit,
+                node: mine,
+                resource: Resource::Iron,
+                amount: 1
+            }]
+        );
+
+        let update = game_update(update.state, 0.1, &GameInput::default());
+        assert_eq!(update.events[0].tick, 1);
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::Deposited {
+                unit: unit,
+                target: Entity(1),
+                resource: Resource::Iron,
+                amount: 1
+            }]
+        );
+
+        let update = game_update(update.state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![
+                GameEventKind::Harvested {
+                    unit: unit,
+                    node: mine,
+                    resource: Resource::Iron,
+                    amount: 1
+                },
+                GameEventKind::MineDepleted { node: mine },
+            ]
+        );
+
+        let update = run_ticks(update.state, 1);
+        let update = game_update(update, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::HarvestFailed {
+                unit: unit,
+                node: mine,
+                reason: TransferFailure::SourceEmpty
+            }]
+        );
+        assert_eq!(update.state.get_tick(), 5);
+    }
+
+    #[test]
+    fn test_spawn_and_rejected_program_events() {
+        let (mut game_state, _, unit) = program_state(100, vec![]);
+        *game_state.positions.get_mut(&unit).unwrap() = Position::new(8, 8);
+
+        let mut game_input = GameInput::default();
+        game_input
+            .user_commands
+            .push(UserCommand::LoadProgram(unit, vec![Command::Halt]));
+        game_input.create_unit = true;
+        let update = game_update(game_state, 0.1, &game_input);
+
+        assert_eq!(
+            event_kinds(&update),
+            vec![
+                GameEventKind::ProgramRejected {
+                    unit: unit,
+                    error: UserCommandError::OutOfHiveRange {
+                        unit: unit,
+                        distance: 16
+                    }
+                },
+                GameEventKind::UnitSpawned { unit: Entity(4) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_label_event() {
+        let (game_state, _, unit) = program_state(100, vec![Command::Jump("nowhere".to_string())]);
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::UnknownLabel {
+                unit: unit,
+                label: "nowhere".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_user_command_errors() {
+        let (game_state, mine, unit) = program_state(100, vec![]);
+        let check =
+            |command: UserCommand| check_user_command(&game_state, &Position::new(0, 0), &command);
+
+        assert_eq!(
+            check(UserCommand::LoadProgram(unit, vec![Command::Halt])),
+            Ok(())
+        );
+        assert_eq!(
+            check(UserCommand::LoadProgram(Entity(42), vec![Command::Halt])),
+            Err(UserCommandError::UnknownEntity(Entity(42)))
+        );
+        assert_eq!(
+            check(UserCommand::LoadCommand(mine, Command::Halt)),
+            Err(UserCommandError::NotProgrammable(mine))
+        );
+        assert_eq!(
+            check(UserCommand::LoadProgram(unit, vec![])),
+            Err(UserCommandError::EmptyProgram(unit))
+        );
+        assert_eq!(
+            check(UserCommand::LoadScript(unit, "  \n".to_string())),
+            Err(UserCommandError::EmptyProgram(unit))
+        );
+    }
+
+    #[test]
+    fn test_user_commands_stop_on_first_error() {
+        let (game_state, _, unit) = program_state(100, vec![]);
+        let mut game_input = GameInput::default();
+        game_input
+            .user_commands
+            .push(UserCommand::LoadCommand(unit, Command::Wait(1)));
+        game_input
+            .user_commands
+            .push(UserCommand::LoadCommand(Entity(42), Command::Halt));
+        game_input
+            .user_commands
+            .push(UserCommand::LoadCommand(unit, Command::Halt));
+        let update = game_update(game_state, 0.1, &game_input);
+
+        assert_eq!(
+            update.command_results,
+            vec![
+                Ok(()),
+                Err(UserCommandError::UnknownEntity(Entity(42))),
+                Err(UserCommandError::Skipped),
+            ]
+        );
+        // only the command before the error was loaded.
+        assert_eq!(
+            update.state.memory.get(&unit).unwrap().commands,
+            vec![Command::Wait(1)]
+        );
+    }
+
+    #[test]
+    fn test_solid_container_capacity() {
+        let mut container = SolidContainer::default();
+        container.capacities.insert(Resource::Copper, 3);
+
+        assert_eq!(container.add(Resource::Copper, 2), 2);
+        assert_eq!(container.add(Resource::Copper, 2), 1);
+        assert_eq!(container.space(Resource::Copper), 0);
+        assert_eq!(container.add(Resource::Iron, 7), 7);
+        assert_eq!(container.total(), 10);
+
+        assert_eq!(container.take(Resource::Iron, 9), 7);
+        assert_eq!(container.first_held(), Some(Resource::Copper));
+        assert_eq!(container.take(Resource::Copper, 3), 3);
+        assert_eq!(container.first_held(), None);
+    }
+
+    #[test]
+    fn test_harvest_mixed_mine() {
+        let (mut game_state, mine, unit) = program_state(
+            1,
+            vec![
+                Command::Harvest(Entity(2), Resource::Copper),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Halt,
+            ],
+        );
+        game_state
+            .solid_containers
+            .get_mut(&mine)
+            .unwrap()
+            .set(Resource::Copper, 1);
+
+        let game_state = run_ticks(game_state, 2);
+        assert_eq!(
+            game_state.get_resource_count(&unit, Resource::Copper),
+            Some(1)
+        );
+        assert_eq!(
+            game_state.get_resource_count(&unit, Resource::Iron),
+            Some(1)
+        );
+        assert_eq!(game_state.get_mineable_count(&mine), Some(0));
+    }
+
+    #[test]
+    fn test_harvest_missing_resource_and_full_target() {
+        let (mut game_state, mine, unit) = program_state(
+            5,
+            vec![
+                Command::Harvest(Entity(2), Resource::Copper),
+                Command::Harvest(Entity(2), Resource::Iron),
+            ],
+        );
+        game_state
+            .solid_containers
+            .get_mut(&unit)
+            .unwrap()
+            .capacities
+            .insert(Resource::Iron, 0);
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::HarvestFailed {
+                unit: unit,
+                node: mine,
+                reason: TransferFailure::SourceEmpty
+            }]
+        );
+        let update = game_update(update.state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::HarvestFailed {
+                unit: unit,
+                node: mine,
+                reason: TransferFailure::TargetFull
+            }]
+        );
+        assert_eq!(update.state.get_mineable_count(&mine), Some(5));
+    }
+
+    #[test]
+    fn test_container_load() {
+        let mut container = SolidContainer {
+            max_load: Some(5),
+            ..SolidContainer::default()
+        };
+
+        assert_eq!(container.add(Resource::Copper, 3), 2);
+        assert_eq!(container.load(), 4);
+        assert_eq!(container.space(Resource::Copper), 0);
+        assert_eq!(container.space(Resource::Iron), 1);
+        assert!(!container.is_full());
+        assert_eq!(container.add(Resource::Iron, 3), 1);
+        assert!(container.is_full());
+    }
+
+    #[test]
+    fn test_harvest_overloaded() {
+        let (game_state, mine, unit) = program_state(
+            UNIT_CARGO_CAPACITY + 5,
+            vec![Command::Harvest(Entity(2), Resource::Iron)],
+        );
+        let mut update = game_update(game_state, 0.1, &GameInput::default());
+        for _ in 1..UNIT_CARGO_CAPACITY {
+            update = game_update(update.state, 0.1, &GameInput::default());
+        }
+        assert_eq!(
+            update.state.get_mineable_count(&unit),
+            Some(UNIT_CARGO_CAPACITY)
+        );
+
+        let update = game_update(update.state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::HarvestFailed {
+                unit: unit,
+                node: mine,
+                reason: TransferFailure::Overloaded
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unit_speed_depends_on_load() {
+        let mut container = SolidContainer::default();
+        assert_eq!(unit_speed(None), UNIT_BASE_SPEED);
+        assert_eq!(unit_speed(Some(&container)), UNIT_BASE_SPEED);
+
+        container.max_load = Some(10);
+        container.set(Resource::Copper, 5);
+        assert_eq!(unit_speed(Some(&container)), UNIT_BASE_SPEED * 0.5);
+
+        // a loaded unit needs longer for the same trip.
+        let trip = |load: u32| {
+            let (mut game_state, _, unit) =
+                program_state(0, vec![Command::MoveD(Position::new(0, 3)), Command::Halt]);
+            game_state
+                .solid_containers
+                .get_mut(&unit)
+                .unwrap()
+                .set(Resource::Iron, load);
+            let mut ticks = 0;
+            while !game_state.memory.get(&unit).unwrap().halted {
+                game_state = run_ticks(game_state, 1);
+                ticks += 1;
+            }
+            ticks
+        };
+        assert!(trip(UNIT_CARGO_CAPACITY) > trip(0));
+    }
+
+    #[test]
+    fn test_energy_spent_and_depleted() {
+        let (mut game_state, mine, unit) = program_state(
+            100,
+            vec![
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Deposit(Entity(1)),
+            ],
+        );
+        game_state.energy.recharge = 0;
+        game_state.energy_levels.get_mut(&unit).unwrap().value = 5;
+
+        // harvest 2, deposit 1, harvest 2, then nothing left for the deposit.
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(update.state.energy_levels.get(&unit).unwrap().value, 3);
+        let game_state = run_ticks(update.state, 2);
+        assert_eq!(game_state.energy_levels.get(&unit).unwrap().value, 0);
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::OutOfEnergy { unit: unit }]
+        );
+        // reported once, the unit just waits after that.
+        let update = game_update(update.state, 0.1, &GameInput::default());
+        assert_eq!(event_kinds(&update), vec![]);
+        assert_eq!(update.state.get_mineable_count(&mine), Some(98));
+        assert_eq!(update.state.get_mineable_count(&unit), Some(1));
+    }
+
+    #[test]
+    fn test_energy_recharges_at_hive() {
+        let (mut game_state, _, unit) =
+            program_state(100, vec![Command::Harvest(Entity(2), Resource::Iron)]);
+        game_state.energy.harvest = 50;
+        {
+            let energy = game_state.energy_levels.get_mut(&unit).unwrap();
+            energy.value = 0;
+            energy.depleted = true;
+        }
+
+        // 5 per tick, the 10th tick has enough for a harvest.
+        let game_state = run_ticks(game_state, 9);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(0));
+        assert_eq!(game_state.energy_levels.get(&unit).unwrap().value, 45);
+        let game_state = run_ticks(game_state, 1);
+        assert_eq!(game_state.get_mineable_count(&unit), Some(1));
+        assert_eq!(game_state.energy_levels.get(&unit).unwrap().value, 0);
+
+        // away from the hive nothing happens.
+        let (mut game_state, _, unit) = program_state(100, vec![Command::Halt]);
+        *game_state.positions.get_mut(&unit).unwrap() = Position::new(3, 3);
+        game_state.energy_levels.get_mut(&unit).unwrap().value = 1;
+        let game_state = run_ticks(game_state, 3);
+        assert_eq!(game_state.energy_levels.get(&unit).unwrap().value, 1);
+    }
+
+    #[test]
+    fn test_moving_costs_energy_per_tile() {
+        let (mut game_state, _, unit) =
+            program_state(0, vec![Command::MoveD(Position::new(0, 3)), Command::Halt]);
+        game_state.energy.recharge = 0;
+        game_state.energy.move_d = 4;
+        while !game_state.memory.get(&unit).unwrap().halted {
+            game_state = run_ticks(game_state, 1);
+        }
+        // MoveD stops just short of the destination tile.
+        let tiles = game_state.get_entity_position(&unit).get_y() - 1;
+        assert!(tiles > 0);
+        assert_eq!(
+            game_state.energy_levels.get(&unit).unwrap().value,
+            UNIT_MAX_ENERGY - 4 * tiles
+        );
+    }
+
+    #[test]
+    fn test_move_blocked_by_mine() {
+        let (mut game_state, mine, unit) =
+            program_state(10, vec![Command::MoveP(Position::new(1, 1)), Command::Halt]);
+        *game_state.collision.create(&mine) = Collision::solid(NODE_RADIUS);
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::MoveBlocked {
+                unit: unit,
+                position: Position::new(1, 1)
+            }]
+        );
+        assert_eq!(update.state.get_entity_position(&unit), Position::new(0, 1));
+    }
+
+    #[test]
+    fn test_move_d_stops_next_to_taken_destination() {
+        let (mut game_state, mine, unit) = program_state(
+            10,
+            vec![
+                Command::MoveD(Position::new(1, 1)),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Halt,
+            ],
+        );
+        *game_state.collision.create(&mine) = Collision::solid(NODE_RADIUS);
+
+        for _ in 0..200 {
+            game_state = run_ticks(game_state, 1);
+        }
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+        assert_eq!(
+            game_state.get_resource_count(&unit, Resource::Iron),
+            Some(1)
+        );
+
+        let unit_circle = game_state
+            .collision
+            .get(&unit)
+            .unwrap()
+            .circle_at(&game_state.get_entity_position(&unit));
+        let mine_circle = game_state
+            .collision
+            .get(&mine)
+            .unwrap()
+            .circle_at(&game_state.get_entity_position(&mine));
+        assert!(!collides(&unit_circle, &mine_circle));
+    }
+
+    #[test]
+    fn test_units_moving_into_each_other() {
+        let (mut game_state, _, older) = program_state(0, vec![]);
+        *game_state.positions.get_mut(&older).unwrap() = Position::new(0, 2);
+        let newer = super::spawn_unit(&mut game_state, Position::new(2, 2));
+        for unit in [older, newer].iter() {
+            game_state.memory.get_mut(unit).unwrap().commands =
+                vec![Command::MoveP(Position::new(1, 2))];
+        }
+
+        // the older unit moves first and takes the spot.
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::MoveBlocked {
+                unit: newer,
+                position: Position::new(1, 2)
+            }]
+        );
+        assert_eq!(
+            update.state.get_entity_position(&older),
+            Position::new(1, 2)
+        );
+        assert_eq!(
+            update.state.get_entity_position(&newer),
+            Position::new(2, 2)
+        );
+    }
+
+    #[test]
+    fn test_overlapping_units_can_separate() {
+        let (mut game_state, _, unit) = program_state(0, vec![]);
+        let other = super::spawn_unit(&mut game_state, Position::new(0, 1));
+        game_state.memory.get_mut(&other).unwrap().commands =
+            vec![Command::MoveP(Position::new(0, 2))];
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert!(event_kinds(&update).is_empty());
+        assert_eq!(update.state.get_entity_position(&unit), Position::new(0, 1));
+        assert_eq!(
+            update.state.get_entity_position(&other),
+            Position::new(0, 2)
+        );
+    }
+
+    #[test]
+    fn test_spawn_blocked_by_unit() {
+        let (game_state, _, _) = program_state(0, vec![]);
+        let mut game_input = GameInput::default();
+        game_input.create_unit = true;
+
+        let update = game_update(game_state, 0.1, &game_input);
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::SpawnBlocked {
+                position: Position::new(0, 1)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_spawn_next_to_hive() {
+        let mut game_state = GameState::new();
+        game_state.create_hive(10, 10);
+        let mut game_input = GameInput::default();
+        game_input.create_unit = true;
+
+        let update = game_update(game_state, 0.1, &game_input);
+        let unit = match event_kinds(&update).as_slice() {
+            [GameEventKind::UnitSpawned { unit }] => *unit,
+            other => panic!("expected a spawn, got {:?}", other),
+        };
+        assert_eq!(
+            update.state.get_entity_position(&unit),
+            Position::new(10, 11)
+        );
+
+        // the spawned unit is in range of the hive, so it can be programmed.
+        let mut game_input = GameInput::default();
+        game_input
+            .user_commands
+            .push(UserCommand::LoadProgram(unit, vec![Command::Halt]));
+        let update = game_update(update.state, 0.1, &game_input);
+        assert_eq!(update.command_results, vec![Ok(())]);
+    }
+
+    #[test]
+    fn test_spawn_skips_unwalkable_tiles() {
+        let mut game_state = GameState::new();
+        game_state.tile_map = TileMap::new(WorldBounds {
+            width: 3,
+            height: 3,
+            layout: GridLayout::Square,
+        });
+        // hive in the bottom corner with the tile above it walled off.
+        game_state.create_hive(2, 2);
+        game_state.tile_map.get_mut(2, 1).unwrap().walkable = false;
+        assert_eq!(
+            spawn_tile(&game_state.tile_map, &Position::new(2, 2)),
+            Some(Position::new(1, 2))
+        );
+
+        game_state.tile_map.get_mut(1, 2).unwrap().walkable = false;
+        let mut game_input = GameInput::default();
+        game_input.create_unit = true;
+        let update = game_update(game_state, 0.1, &game_input);
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::SpawnBlocked {
+                position: Position::new(2, 2)
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hex_positions() {
+        assert_eq!(Position::new(3, 2).hex(), (2, 2));
+        assert_eq!(Position::from_hex(2, 2), Some(Position::new(3, 2)));
+        assert_eq!(Position::from_hex(-1, 0), None);
+
+        assert_eq!(GridLayout::Square.tile_distance(1, 1, 3, 2), 3);
+        assert_eq!(GridLayout::Hex.tile_distance(1, 1, 3, 2), 2);
+    }
+
+    #[test]
+    fn test_hex_harvest_range() {
+        for (layout, harvested) in [(GridLayout::Square, 0), (GridLayout::Hex, 1)].iter() {
+            let (mut game_state, _, unit) =
+                program_state(10, vec![Command::Harvest(Entity(2), Resource::Iron)]);
+            game_state.tile_map.bounds.layout = *layout;
+            *game_state.positions.get_mut(&unit).unwrap() = Position::new(3, 2);
+
+            let game_state = run_ticks(game_state, 1);
+            assert_eq!(
+                game_state.get_resource_count(&unit, Resource::Iron),
+                Some(*harvested)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tile_map() {
+        let mut tile_map = TileMap::new(WorldBounds {
+            width: 3,
+            height: 2,
+            layout: GridLayout::Square,
+        });
+        assert!(tile_map.is_walkable(2, 1));
+        assert!(!tile_map.is_walkable(3, 1));
+        assert!(tile_map.get(0, 2).is_none());
+
+        tile_map.get_mut(2, 1).unwrap().walkable = false;
+        tile_map.get_mut(1, 0).unwrap().deposit = Some(Resource::Copper);
+        assert!(!tile_map.is_walkable(2, 1));
+        assert_eq!(
+            tile_map
+                .changed_tiles()
+                .iter()
+                .map(|(p, _)| *p)
+                .collect::<Vec<_>>(),
+            vec![(1, 0), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_moves_stay_on_walkable_tiles() {
+        let (mut game_state, _, unit) = program_state(
+            0,
+            vec![
+                Command::MoveP(Position::new(1, 1)),
+                Command::MoveP(Position::new(0, 2)),
+                Command::MoveD(Position::new(0, 3)),
+                Command::Halt,
+            ],
+        );
+        game_state.tile_map = TileMap::new(WorldBounds {
+            width: 3,
+            height: 3,
+            layout: GridLayout::Square,
+        });
+        game_state.tile_map.get_mut(1, 1).unwrap().walkable = false;
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::MoveBlocked {
+                unit: unit,
+                position: Position::new(1, 1)
+            }]
+        );
+
+        // MoveD gives up on a destination off the map once it reaches the edge.
+        let game_state = run_ticks(update.state, 200);
+        assert_eq!(game_state.get_entity_position(&unit).get_y(), 2);
+        assert!(game_state.memory.get(&unit).unwrap().halted);
+    }
+
+    #[test]
+    fn test_rough_terrain_costs_energy() {
+        let (mut game_state, _, unit) = program_state(0, vec![Command::MoveP(Position::new(0, 2))]);
+        game_state.tile_map.get_mut(0, 2).unwrap().terrain_cost = 3;
+        let game_state = run_ticks(game_state, 1);
+        assert_eq!(game_state.get_entity_position(&unit), Position::new(0, 2));
+        assert_eq!(
+            game_state.energy_levels.get(&unit).unwrap().value,
+            UNIT_MAX_ENERGY - 3 * EnergyConfig::default().move_p
+        );
+
+        // tiles set up outside of levels can cost more than the unit could ever pay.
+        let (mut game_state, _, unit) = program_state(0, vec![Command::MoveP(Position::new(0, 2))]);
+        game_state.tile_map.get_mut(0, 2).unwrap().terrain_cost = u32::MAX;
+        let game_state = run_ticks(game_state, 1);
+        assert_eq!(game_state.get_entity_position(&unit), Position::new(0, 1));
+        assert_eq!(
+            game_state.energy_levels.get(&unit).unwrap().value,
+            UNIT_MAX_ENERGY
+        );
+    }
+
+    #[test]
+    fn test_rough_terrain_needs_energy_up_front() {
+        let (mut game_state, _, unit) = program_state(0, vec![Command::MoveP(Position::new(0, 2))]);
+        game_state.tile_map.get_mut(0, 2).unwrap().terrain_cost = 3;
+        game_state.energy.recharge = 0;
+        // enough for plain ground but not for the rough tile.
+        let move_p = EnergyConfig::default().move_p;
+        game_state.energy_levels.get_mut(&unit).unwrap().value = 2 * move_p;
+
+        let update = game_update(game_state, 0.1, &GameInput::default());
+        assert_eq!(
+            event_kinds(&update),
+            vec![GameEventKind::OutOfEnergy { unit: unit }]
+        );
+        assert_eq!(update.state.get_entity_position(&unit), Position::new(0, 1));
+        assert_eq!(
+            update.state.energy_levels.get(&unit).unwrap().value,
+            2 * move_p
+        );
+    }
 }
@@ -0,0 +1,351 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::assembly;
+use crate::autopilot::Autopilot;
+use crate::events::{GameEvent, GameEventKind};
+use crate::game_state;
+use crate::game_state::GameState;
+use crate::scripting::{ScriptEngine, UnitScripts, DEFAULT_INSTRUCTION_BUDGET};
+use crate::snapshot;
+
+/// settings for running the simulation without a display.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadlessOptions {
+    /// number of game_update calls to perform.
+    pub ticks: u32,
+    /// fixed time step handed to every game_update call.
+    pub dt: f32,
+    /// where to write the final game state, printed to stdout if None.
+    pub output: Option<PathBuf>,
+    /// level file to start from instead of the default level.
+    pub level: Option<PathBuf>,
+    /// snapshot to continue from instead of the default level.
+    pub resume: Option<PathBuf>,
+    /// where to save a snapshot of the final game state.
+    pub snapshot: Option<PathBuf>,
+    /// lua control script used instead of the built in autopilot.
+    pub script: Option<PathBuf>,
+    /// where to write the game events, one per line.
+    pub events: Option<PathBuf>,
+    /// add the program of every unit to the final game state, see assembly::disassemble_units.
+    pub disassemble: bool,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            ticks: 20000,
+            dt: 0.1,
+            output: None,
+            level: None,
+            resume: None,
+            snapshot: None,
+            script: None,
+            events: None,
+            disassemble: false,
+        }
+    }
+}
+
+const USAGE: &str = "usage: rminions --headless [--ticks N] [--dt SECONDS] [--output FILE] \
+                     [--level LEVEL] [--resume SNAPSHOT] [--snapshot SNAPSHOT] \
+                     [--script LUA] [--events FILE] [--disassemble]";
+
+/// parses the command line arguments (excluding the program name).
+/// returns None if headless mode wasn't asked for.
+pub fn parse_args(args: &[String]) -> Result<Option<HeadlessOptions>, String> {
+    let mut headless = false;
+    let mut options = HeadlessOptions::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => headless = true,
+            "--disassemble" => options.disassemble = true,
+            "--ticks" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--ticks needs a value\n{}", USAGE))?;
+                options.ticks = value
+                    .parse()
+                    .map_err(|_| format!("invalid tick count: {}", value))?;
+            }
+            "--dt" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--dt needs a value\n{}", USAGE))?;
+                options.dt = value
+                    .parse()
+                    .map_err(|_| format!("invalid dt: {}", value))?;
+            }
+            "--output" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--output needs a value\n{}", USAGE))?;
+                options.output = Some(PathBuf::from(value));
+            }
+            "--level" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--level needs a value\n{}", USAGE))?;
+                options.level = Some(PathBuf::from(value));
+            }
+            "--resume" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--resume needs a value\n{}", USAGE))?;
+                options.resume = Some(PathBuf::from(value));
+            }
+            "--snapshot" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--snapshot needs a value\n{}", USAGE))?;
+                options.snapshot = Some(PathBuf::from(value));
+            }
+            "--script" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--script needs a value\n{}", USAGE))?;
+                options.script = Some(PathBuf::from(value));
+            }
+            "--events" => {
+                let value = iter
+                    .next()
+                    .ok_or(format!("--events needs a value\n{}", USAGE))?;
+                options.events = Some(PathBuf::from(value));
+            }
+            _ => return Err(format!("unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    if headless {
+        Ok(Some(options))
+    } else {
+        Ok(None)
+    }
+}
+
+/// what a headless run ended with.
+pub struct Simulation {
+    pub state: GameState,
+    /// events of every frame, in order.
+    pub events: Vec<GameEvent>,
+}
+
+/// hands out the next command of every unit running a lua program.
+/// scripts that fail are reported as events of the coming update.
+fn step_unit_scripts(
+    unit_scripts: &mut UnitScripts,
+    current_state: &mut GameState,
+) -> Vec<GameEvent> {
+    let tick = current_state.get_tick();
+    unit_scripts
+        .step(current_state)
+        .into_iter()
+        .map(|(entity, e)| GameEvent {
+            tick: tick,
+            kind: GameEventKind::ScriptFailed {
+                unit: entity,
+                message: e.to_string(),
+            },
+        })
+        .collect()
+}
+
+/// steps the loaded level for options.ticks frames.
+pub fn simulate(mut current_state: GameState, options: &HeadlessOptions) -> Simulation {
+    let mut autopilot = Autopilot::new();
+    let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+    let mut events = Vec::new();
+
+    for frame in 0..options.ticks {
+        let mut game_input = game_state::GameInput::default();
+        autopilot.update(&current_state, frame, &mut game_input);
+        events.extend(step_unit_scripts(&mut unit_scripts, &mut current_state));
+        let update = game_state::game_update(current_state, options.dt, &game_input);
+        current_state = update.state;
+        events.extend(update.events);
+    }
+
+    return Simulation {
+        state: current_state,
+        events: events,
+    };
+}
+
+/// same as simulate, but the control script provides the input every frame.
+pub fn simulate_scripted(
+    mut current_state: GameState,
+    options: &HeadlessOptions,
+    engine: &ScriptEngine,
+) -> Result<Simulation, String> {
+    let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+    let mut events = Vec::new();
+
+    for frame in 0..options.ticks {
+        let mut game_input = game_state::GameInput::default();
+        engine
+            .tick(&current_state, frame, &mut game_input)
+            .map_err(|e| format!("control script failed on frame {}: {}", frame, e))?;
+        events.extend(step_unit_scripts(&mut unit_scripts, &mut current_state));
+        let update = game_state::game_update(current_state, options.dt, &game_input);
+        current_state = update.state;
+        events.extend(update.events);
+    }
+
+    return Ok(Simulation {
+        state: current_state,
+        events: events,
+    });
+}
+
+pub fn run(options: &HeadlessOptions) -> Result<(), String> {
+    let initial_state = match (&options.resume, &options.level) {
+        (Some(_), Some(_)) => return Err("--resume and --level can't be combined".to_string()),
+        (Some(path), None) => {
+            snapshot::load(path).map_err(|e| format!("failed to load {}: {}", path.display(), e))?
+        }
+        (None, Some(path)) => game_state::game_load_from(path)
+            .map_err(|e| format!("failed to load {}: {}", path.display(), e))?,
+        (None, None) => game_state::game_load(),
+    };
+    let result = match &options.script {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+            let engine = ScriptEngine::new(&source)
+                .map_err(|e| format!("failed to load {}: {}", path.display(), e))?;
+            simulate_scripted(initial_state, options, &engine)?
+        }
+        None => simulate(initial_state, options),
+    };
+    let final_state = result.state;
+
+    if let Some(path) = &options.events {
+        let lines: Vec<String> = result.events.iter().map(|e| e.to_string()).collect();
+        fs::write(path, lines.join("\n"))
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    }
+
+    if let Some(path) = &options.snapshot {
+        snapshot::save(&final_state, path)
+            .map_err(|e| format!("failed to save {}: {}", path.display(), e))?;
+    }
+
+    let mut output = final_state.string();
+    if options.disassemble {
+        output += &assembly::disassemble_units(&final_state);
+    }
+    match &options.output {
+        Some(path) => fs::write(path, output)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e)),
+        None => {
+            print!("{}", output);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::Position;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_no_headless() {
+        assert_eq!(parse_args(&args(&[])), Ok(None));
+    }
+
+    #[test]
+    fn parse_headless_options() {
+        let options = parse_args(&args(&["--headless", "--ticks", "10", "--dt", "0.5"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.ticks, 10);
+        assert_eq!(options.dt, 0.5);
+        assert_eq!(options.output, None);
+        assert!(!options.disassemble);
+
+        let options = parse_args(&args(&["--headless", "--disassemble"]))
+            .unwrap()
+            .unwrap();
+        assert!(options.disassemble);
+    }
+
+    #[test]
+    fn parse_bad_ticks() {
+        assert!(parse_args(&args(&["--headless", "--ticks", "many"])).is_err());
+        assert!(parse_args(&args(&["--headless", "--ticks"])).is_err());
+        assert!(parse_args(&args(&["--bogus"])).is_err());
+    }
+
+    #[test]
+    fn simulate_spawns_and_harvests() {
+        let options = HeadlessOptions {
+            ticks: 200,
+            dt: 0.1,
+            ..HeadlessOptions::default()
+        };
+        let result = simulate(game_state::game_load(), &options);
+
+        // autopilot spawns a single unit on frame 2.
+        assert_eq!(result.state.get_programable_units().len(), 1);
+        assert_eq!(result.state.get_tick(), 200);
+
+        let spawned: Vec<_> = result
+            .events
+            .iter()
+            .filter(|e| match e.kind {
+                GameEventKind::UnitSpawned { .. } => true,
+                _ => false,
+            })
+            .collect();
+        assert_eq!(spawned.len(), 1);
+        assert_eq!(spawned[0].tick, 2);
+    }
+
+    #[test]
+    fn simulate_reports_failed_unit_scripts() {
+        let mut game_state = game_state::game_load();
+        let unit = game_state::spawn_unit(&mut game_state, Position::new(5, 5));
+        game_state.memory.get_mut(&unit).unwrap().script = Some("harvest(".to_string());
+        let options = HeadlessOptions {
+            ticks: 1,
+            ..HeadlessOptions::default()
+        };
+
+        let result = simulate(game_state, &options);
+        let failed: Vec<_> = result
+            .events
+            .iter()
+            .filter(|e| match &e.kind {
+                GameEventKind::ScriptFailed { unit: u, .. } => *u == unit,
+                _ => false,
+            })
+            .collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].tick, 0);
+    }
+
+    #[test]
+    fn simulate_harvests_into_a_moved_hive() {
+        let game_state = crate::level::from_str(
+            r#"(world: (width: 20, height: 20), hive: (x: 10, y: 10),
+                nodes: [(name: "iron", x: 12, y: 10, resource: "iron", amount: 50)])"#,
+        )
+        .unwrap();
+        let hive = game_state.get_hive().unwrap();
+        let options = HeadlessOptions {
+            ticks: 300,
+            ..HeadlessOptions::default()
+        };
+
+        let result = simulate(game_state, &options);
+        assert!(result.state.get_mineable_count(&hive).unwrap() > 0);
+    }
+}
@@ -0,0 +1,739 @@
+//! Level definition files.
+//!
+//! A level is a RON file describing the starting world:
+//!
+//! ```text
+//! (
+//!     world: (width: 20, height: 20),
+//!     hive: (x: 0, y: 0),
+//!     energy: (move_d: 1, harvest: 2, recharge: 5),
+//!     nodes: [
+//!         (name: "small_iron", x: 10, y: 5, resource: "iron", amount: 2),
+//!         (name: "big_iron", x: 5, y: 10, resource: "iron", amount: 900),
+//!         (name: "mixed", x: 8, y: 2, deposits: {"iron": 20, "copper": 5}),
+//!     ],
+//!     units: [
+//!         (x: 0, y: 1, program: [
+//!             Label("top"),
+//!             MoveD(5, 10),
+//!             Harvest("big_iron", "iron"),
+//!             JumpIf(MineEmpty("big_iron"), "done"),
+//!             MoveD(0, 0),
+//!             Deposit("hive"),
+//!             Jump("top"),
+//!             Label("done"),
+//!             Halt,
+//!         ]),
+//!     ],
+//! )
+//! ```
+//!
+//! A node holds either `amount` of a single `resource` or several resources
+//! listed in `deposits`. Programs refer to nodes by name, `"hive"` is reserved
+//! for the hive.
+//! Instead of `program` a unit can give `program_file: Some("harvest.asm")`,
+//! a text program (see `assembly`) relative to the level file.
+//! `nodes` and `units` may be left out for an empty world. `energy` sets what
+//! commands cost (see `EnergyConfig`), left out fields keep their defaults.
+//! `world: (width: 20, height: 20, layout: Hex)` makes a world of hexagons,
+//! without `layout` tiles are square (see `GridLayout`).
+//! Every tile is plain walkable ground unless listed in `tiles`, e.g.
+//! `tiles: [(x: 3, y: 3, walkable: false), (x: 4, y: 3, terrain_cost: 3)]`.
+//! Nothing may be placed on or moved to a tile that isn't walkable.
+//! terrain_cost goes from 1 to `MAX_TERRAIN_COST`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::assembly;
+use crate::entity_manager::Entity;
+use crate::game_state::{
+    spawn_unit, Collision, Command, Condition, EnergyConfig, GameState, MineableNode, Position,
+    TileMap, WorldBounds, MAX_TERRAIN_COST, MAX_WORLD_TILES, NODE_RADIUS,
+};
+use crate::resource::Resource;
+
+/// name programs use to refer to the hive.
+pub const HIVE_NAME: &str = "hive";
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    /// file isn't valid RON or is missing required fields.
+    Parse(String),
+    /// a specific entry of the level is invalid, entry is e.g. "nodes[2] (big_iron)".
+    Entry {
+        entry: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "failed to read level: {}", e),
+            LevelError::Parse(e) => write!(f, "failed to parse level: {}", e),
+            LevelError::Entry { entry, message } => write!(f, "{}: {}", entry, message),
+        }
+    }
+}
+
+impl From<io::Error> for LevelError {
+    fn from(e: io::Error) -> Self {
+        LevelError::Io(e)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TilePos {
+    x: u32,
+    y: u32,
+}
+
+fn default_walkable() -> bool {
+    true
+}
+
+fn default_terrain_cost() -> u32 {
+    1
+}
+
+/// a tile that isn't plain walkable ground.
+#[derive(Debug, Deserialize)]
+struct TileDef {
+    x: u32,
+    y: u32,
+    #[serde(default = "default_walkable")]
+    walkable: bool,
+    #[serde(default = "default_terrain_cost")]
+    terrain_cost: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeDef {
+    name: String,
+    x: u32,
+    y: u32,
+    // empty when the node lists deposits instead.
+    #[serde(default)]
+    resource: String,
+    #[serde(default)]
+    amount: u32,
+    #[serde(default)]
+    deposits: BTreeMap<String, u32>,
+}
+
+/// program commands as written in a level, targets are names rather than entities.
+#[derive(Debug, Deserialize)]
+enum CommandDef {
+    MoveP(u32, u32),
+    MoveD(u32, u32),
+    Harvest(String, String),
+    Deposit(String),
+    Label(String),
+    Jump(String),
+    JumpIf(ConditionDef, String),
+    Halt,
+    Wait(u32),
+}
+
+#[derive(Debug, Deserialize)]
+enum ConditionDef {
+    CargoFull,
+    CargoEmpty,
+    MineEmpty(String),
+    EnergyLow,
+}
+
+#[derive(Debug, Deserialize)]
+struct UnitDef {
+    x: u32,
+    y: u32,
+    #[serde(default)]
+    program: Vec<CommandDef>,
+    #[serde(default)]
+    program_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LevelDef {
+    world: WorldBounds,
+    hive: TilePos,
+    #[serde(default)]
+    energy: EnergyConfig,
+    #[serde(default)]
+    tiles: Vec<TileDef>,
+    #[serde(default)]
+    nodes: Vec<NodeDef>,
+    #[serde(default)]
+    units: Vec<UnitDef>,
+}
+
+fn entry_error(entry: &str, message: String) -> LevelError {
+    LevelError::Entry {
+        entry: entry.to_string(),
+        message: message,
+    }
+}
+
+fn check_tile(
+    entry: &str,
+    x: u32,
+    y: u32,
+    tile_map: &TileMap,
+    occupied: &mut HashMap<(u32, u32), String>,
+) -> Result<(), LevelError> {
+    let world = tile_map.bounds();
+    if !world.contains(x, y) {
+        return Err(entry_error(
+            entry,
+            format!(
+                "position ({}, {}) is outside the {}x{} world",
+                x, y, world.width, world.height
+            ),
+        ));
+    }
+    if !tile_map.is_walkable(x, y) {
+        return Err(entry_error(
+            entry,
+            format!("position ({}, {}) is not walkable", x, y),
+        ));
+    }
+    if let Some(other) = occupied.get(&(x, y)) {
+        return Err(entry_error(
+            entry,
+            format!("position ({}, {}) is already used by {}", x, y, other),
+        ));
+    }
+    occupied.insert((x, y), entry.to_string());
+    Ok(())
+}
+
+fn parse_resource(entry: &str, name: &str) -> Result<Resource, LevelError> {
+    Resource::from_name(name).ok_or(entry_error(
+        entry,
+        format!("unknown resource \"{}\" (expected iron or copper)", name),
+    ))
+}
+
+fn resolve_command(
+    entry: &str,
+    command: &CommandDef,
+    tile_map: &TileMap,
+    names: &HashMap<String, Entity>,
+) -> Result<Command, LevelError> {
+    let lookup = |name: &String| match names.get(name) {
+        Some(e) => Ok(*e),
+        None => Err(entry_error(entry, format!("unknown target \"{}\"", name))),
+    };
+    let check = |x: u32, y: u32| {
+        if !tile_map.bounds().contains(x, y) {
+            Err(entry_error(
+                entry,
+                format!("move to ({}, {}) leaves the world", x, y),
+            ))
+        } else if !tile_map.is_walkable(x, y) {
+            Err(entry_error(
+                entry,
+                format!("move to ({}, {}) is not walkable", x, y),
+            ))
+        } else {
+            Ok(Position::new(x, y))
+        }
+    };
+
+    match command {
+        CommandDef::MoveP(x, y) => Ok(Command::MoveP(check(*x, *y)?)),
+        CommandDef::MoveD(x, y) => Ok(Command::MoveD(check(*x, *y)?)),
+        CommandDef::Harvest(name, resource) => Ok(Command::Harvest(
+            lookup(name)?,
+            parse_resource(entry, resource)?,
+        )),
+        CommandDef::Deposit(name) => Ok(Command::Deposit(lookup(name)?)),
+        CommandDef::Label(label) => Ok(Command::Label(label.clone())),
+        CommandDef::Jump(label) => Ok(Command::Jump(label.clone())),
+        CommandDef::JumpIf(condition, label) => {
+            let condition = match condition {
+                ConditionDef::CargoFull => Condition::CargoFull,
+                ConditionDef::CargoEmpty => Condition::CargoEmpty,
+                ConditionDef::MineEmpty(name) => Condition::MineEmpty(lookup(name)?),
+                ConditionDef::EnergyLow => Condition::EnergyLow,
+            };
+            Ok(Command::JumpIf(condition, label.clone()))
+        }
+        CommandDef::Halt => Ok(Command::Halt),
+        CommandDef::Wait(ticks) => Ok(Command::Wait(*ticks)),
+    }
+}
+
+/// dir is where program files are looked up.
+fn build(level: LevelDef, dir: &Path) -> Result<GameState, LevelError> {
+    if level.world.width == 0 || level.world.height == 0 {
+        return Err(entry_error(
+            "world",
+            "width and height must be at least 1".to_string(),
+        ));
+    }
+    if level
+        .world
+        .tile_count()
+        .map_or(true, |n| n > MAX_WORLD_TILES)
+    {
+        return Err(entry_error(
+            "world",
+            format!(
+                "{}x{} is more than {} tiles",
+                level.world.width, level.world.height, MAX_WORLD_TILES
+            ),
+        ));
+    }
+
+    let mut game_state = GameState::new();
+    game_state.tile_map = TileMap::new(level.world.clone());
+    game_state.energy = level.energy.clone();
+
+    for (i, tile_def) in level.tiles.iter().enumerate() {
+        let entry = format!("tiles[{}]", i);
+        if tile_def.terrain_cost == 0 || tile_def.terrain_cost > MAX_TERRAIN_COST {
+            return Err(entry_error(
+                &entry,
+                format!("terrain_cost must be between 1 and {}", MAX_TERRAIN_COST),
+            ));
+        }
+        let tile = game_state
+            .tile_map
+            .get_mut(tile_def.x, tile_def.y)
+            .ok_or(entry_error(
+                &entry,
+                format!(
+                    "position ({}, {}) is outside the {}x{} world",
+                    tile_def.x, tile_def.y, level.world.width, level.world.height
+                ),
+            ))?;
+        tile.walkable = tile_def.walkable;
+        tile.terrain_cost = tile_def.terrain_cost;
+    }
+
+    let mut occupied = HashMap::new();
+    let mut names = HashMap::new();
+
+    check_tile(
+        "hive",
+        level.hive.x,
+        level.hive.y,
+        &game_state.tile_map,
+        &mut occupied,
+    )?;
+    game_state.create_hive(level.hive.x, level.hive.y);
+    names.insert(HIVE_NAME.to_string(), game_state.get_hive().unwrap());
+
+    for (i, node) in level.nodes.iter().enumerate() {
+        let entry = format!("nodes[{}] ({})", i, node.name);
+        if names.contains_key(&node.name) {
+            return Err(entry_error(
+                &entry,
+                format!("name \"{}\" is already used", node.name),
+            ));
+        }
+        check_tile(&entry, node.x, node.y, &game_state.tile_map, &mut occupied)?;
+
+        let mut deposits = Vec::new();
+        match (node.resource.is_empty(), node.deposits.is_empty()) {
+            (false, true) => {
+                deposits.push((parse_resource(&entry, &node.resource)?, node.amount));
+            }
+            (true, false) => {
+                for (resource, amount) in node.deposits.iter() {
+                    deposits.push((parse_resource(&entry, resource)?, *amount));
+                }
+            }
+            _ => {
+                return Err(entry_error(
+                    &entry,
+                    "needs either resource and amount or deposits".to_string(),
+                ))
+            }
+        }
+
+        // the tile shows what the node holds most of.
+        game_state.tile_map.get_mut(node.x, node.y).unwrap().deposit = deposits
+            .iter()
+            .max_by_key(|(_, amount)| *amount)
+            .map(|(r, _)| *r);
+
+        let node_e = game_state.entity_manager.create();
+        let container = game_state.solid_containers.create(&node_e);
+        for (resource, amount) in deposits {
+            container.set(resource, amount);
+        }
+        let total = container.total();
+        *game_state.positions.create(&node_e) = Position::new(node.x, node.y);
+        *game_state.collision.create(&node_e) = Collision::solid(NODE_RADIUS);
+        *game_state.iron_mines.create(&node_e) = MineableNode {
+            current_amount: total,
+            initial_amount: total,
+        };
+        names.insert(node.name.clone(), node_e);
+    }
+
+    for (i, unit) in level.units.iter().enumerate() {
+        let entry = format!("units[{}]", i);
+        check_tile(&entry, unit.x, unit.y, &game_state.tile_map, &mut occupied)?;
+
+        let mut program = Vec::new();
+        for (j, command) in unit.program.iter().enumerate() {
+            let command_entry = format!("{} program[{}]", entry, j);
+            program.push(resolve_command(
+                &command_entry,
+                command,
+                &game_state.tile_map,
+                &names,
+            )?);
+        }
+        if let Some(file) = &unit.program_file {
+            if !program.is_empty() {
+                return Err(entry_error(
+                    &entry,
+                    "program and program_file can't be combined".to_string(),
+                ));
+            }
+            let source = fs::read_to_string(dir.join(file))
+                .map_err(|e| entry_error(&entry, format!("failed to read {}: {}", file, e)))?;
+            program = assembly::assemble(&source, &names)
+                .map_err(|e| entry_error(&entry, format!("{} {}", file, e)))?;
+        }
+        for (j, command) in program.iter().enumerate() {
+            let label = match command {
+                Command::Jump(l) | Command::JumpIf(_, l) => l,
+                _ => continue,
+            };
+            let found = program.iter().any(|c| match c {
+                Command::Label(other) => other == label,
+                _ => false,
+            });
+            if !found {
+                return Err(entry_error(
+                    &format!("{} program[{}]", entry, j),
+                    format!("unknown label \"{}\"", label),
+                ));
+            }
+        }
+
+        let unit_e = spawn_unit(&mut game_state, Position::new(unit.x, unit.y));
+        game_state.memory.get_mut(&unit_e).unwrap().commands = program;
+    }
+
+    return Ok(game_state);
+}
+
+fn parse(contents: &str, dir: &Path) -> Result<GameState, LevelError> {
+    let level: LevelDef =
+        ron::de::from_str(contents).map_err(|e| LevelError::Parse(e.to_string()))?;
+    build(level, dir)
+}
+
+/// builds a game state from level text, program files are relative to the working directory.
+/// only tests build levels from inline text.
+#[cfg(test)]
+pub fn from_str(contents: &str) -> Result<GameState, LevelError> {
+    parse(contents, Path::new("."))
+}
+
+/// program files are relative to the level file.
+pub fn load(path: &Path) -> Result<GameState, LevelError> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    parse(&fs::read_to_string(path)?, dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::GridLayout;
+
+    const LEVEL: &str = r#"
+(
+    world: (width: 12, height: 8),
+    hive: (x: 1, y: 1),
+    nodes: [
+        (name: "iron_a", x: 10, y: 5, resource: "iron", amount: 2),
+        (name: "copper_a", x: 5, y: 7, resource: "copper", amount: 40),
+    ],
+    units: [
+        (x: 1, y: 2, program: [MoveD(10, 5), Harvest("iron_a", "iron"), MoveD(1, 1), Deposit("hive")]),
+    ],
+)
+"#;
+
+    fn expect_entry_error(level: &str, expected_entry: &str) {
+        match from_str(level) {
+            Err(LevelError::Entry { entry, .. }) => assert_eq!(entry, expected_entry),
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("level should not load"),
+        }
+    }
+
+    #[test]
+    fn level_builds_game_state() {
+        let game_state = from_str(LEVEL).unwrap();
+
+        assert_eq!(game_state.get_world_bounds().width, 12);
+        assert_eq!(game_state.get_world_bounds().height, 8);
+
+        let hive = game_state.get_hive().unwrap();
+        assert_eq!(game_state.get_entity_position(&hive), Position::new(1, 1));
+
+        // hive, two nodes and a unit.
+        assert_eq!(game_state.entity_manager.count(), 4);
+        assert_eq!(game_state.get_mineable_count(&Entity(2)), Some(2));
+        assert_eq!(
+            game_state.get_resource_count(&Entity(3), Resource::Copper),
+            Some(40)
+        );
+
+        let units = game_state.get_programable_units();
+        assert_eq!(units.len(), 1);
+        let memory = game_state.memory.get(units[0]).unwrap();
+        assert_eq!(
+            memory.commands,
+            vec![
+                Command::MoveD(Position::new(10, 5)),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::MoveD(Position::new(1, 1)),
+                Command::Deposit(hive),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_level_file_matches_game_load() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("levels/default.ron");
+        let game_state = load(&path).unwrap();
+        let expected = crate::game_state::game_load();
+
+        assert_eq!(
+            game_state.entity_manager.count(),
+            expected.entity_manager.count()
+        );
+        assert_eq!(game_state.get_mineable_count(&Entity(2)), Some(2));
+        assert_eq!(game_state.get_mineable_count(&Entity(3)), Some(900));
+    }
+
+    #[test]
+    fn level_loads_program_file() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("levels/harvest_loop.ron");
+        let game_state = load(&path).unwrap();
+
+        let memory = game_state.memory.get(&Entity(3)).unwrap();
+        assert_eq!(memory.commands.len(), 9);
+        assert_eq!(
+            memory.commands[3],
+            Command::Harvest(Entity(2), Resource::Iron)
+        );
+        assert_eq!(memory.commands[5], Command::Deposit(Entity(1)));
+    }
+
+    #[test]
+    fn level_rejects_missing_program_file() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                units: [(x: 0, y: 1, program_file: Some("does_not_exist.asm"))])"#,
+            "units[0]",
+        );
+    }
+
+    #[test]
+    fn level_rejects_out_of_bounds_node() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                nodes: [(name: "far", x: 9, y: 1, resource: "iron", amount: 1)])"#,
+            "nodes[0] (far)",
+        );
+    }
+
+    #[test]
+    fn level_energy_costs() {
+        let game_state = from_str(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                energy: (harvest: 7, recharge: 1))"#,
+        )
+        .unwrap();
+        assert_eq!(game_state.energy.harvest, 7);
+        assert_eq!(game_state.energy.recharge, 1);
+        assert_eq!(game_state.energy.move_d, EnergyConfig::default().move_d);
+    }
+
+    #[test]
+    fn level_grid_layout() {
+        let game_state = from_str(LEVEL).unwrap();
+        assert_eq!(game_state.get_world_bounds().layout, GridLayout::Square);
+
+        let game_state =
+            from_str(r#"(world: (width: 4, height: 4, layout: Hex), hive: (x: 0, y: 0))"#).unwrap();
+        assert_eq!(game_state.get_world_bounds().layout, GridLayout::Hex);
+    }
+
+    #[test]
+    fn level_tiles() {
+        let game_state = from_str(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 2, y: 2, walkable: false), (x: 3, y: 2, terrain_cost: 4)],
+                nodes: [(name: "mixed", x: 2, y: 1, deposits: {"iron": 3, "copper": 4})])"#,
+        )
+        .unwrap();
+        let tile_map = game_state.get_tile_map();
+        assert!(!tile_map.is_walkable(2, 2));
+        assert_eq!(tile_map.terrain_cost(3, 2), 4);
+        assert_eq!(tile_map.get(2, 1).unwrap().deposit, Some(Resource::Copper));
+        assert_eq!(tile_map.changed_tiles().len(), 3);
+
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 4, y: 0, walkable: false)])"#,
+            "tiles[0]",
+        );
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 1, y: 0, terrain_cost: 0)])"#,
+            "tiles[0]",
+        );
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 1, y: 1, terrain_cost: 4000000000)])"#,
+            "tiles[0]",
+        );
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 0, y: 0, walkable: false)])"#,
+            "hive",
+        );
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                tiles: [(x: 2, y: 2, walkable: false)],
+                units: [(x: 0, y: 1, program: [MoveP(2, 2)])])"#,
+            "units[0] program[0]",
+        );
+    }
+
+    #[test]
+    fn level_mixed_deposits() {
+        let game_state = from_str(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                nodes: [(name: "mixed", x: 2, y: 1, deposits: {"iron": 3, "copper": 4})])"#,
+        )
+        .unwrap();
+        assert_eq!(
+            game_state.get_resource_count(&Entity(2), Resource::Iron),
+            Some(3)
+        );
+        assert_eq!(
+            game_state.get_resource_count(&Entity(2), Resource::Copper),
+            Some(4)
+        );
+        assert_eq!(
+            game_state
+                .iron_mines
+                .get(&Entity(2))
+                .unwrap()
+                .initial_amount,
+            7
+        );
+
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                nodes: [(name: "both", x: 2, y: 1, resource: "iron", amount: 1,
+                         deposits: {"copper": 4})])"#,
+            "nodes[0] (both)",
+        );
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                units: [(x: 0, y: 1, program: [Harvest("hive", "gold")])])"#,
+            "units[0] program[0]",
+        );
+    }
+
+    #[test]
+    fn level_rejects_unknown_resource() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                nodes: [(name: "gold", x: 2, y: 1, resource: "gold", amount: 1)])"#,
+            "nodes[0] (gold)",
+        );
+    }
+
+    #[test]
+    fn level_rejects_overlapping_entries() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                units: [(x: 0, y: 0)])"#,
+            "units[0]",
+        );
+    }
+
+    #[test]
+    fn level_rejects_unknown_program_target() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                units: [(x: 0, y: 1, program: [Harvest("nowhere", "iron")])])"#,
+            "units[0] program[0]",
+        );
+    }
+
+    #[test]
+    fn level_control_flow_program() {
+        let game_state = from_str(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                nodes: [(name: "iron", x: 2, y: 1, resource: "iron", amount: 1)],
+                units: [(x: 0, y: 1, program: [
+                    Label("top"),
+                    JumpIf(MineEmpty("iron"), "done"),
+                    Wait(2),
+                    Jump("top"),
+                    Label("done"),
+                    Halt,
+                ])])"#,
+        )
+        .unwrap();
+        let memory = game_state.memory.get(&Entity(3)).unwrap();
+        assert_eq!(
+            memory.commands[1],
+            Command::JumpIf(Condition::MineEmpty(Entity(2)), "done".to_string())
+        );
+        assert_eq!(memory.commands[2], Command::Wait(2));
+    }
+
+    #[test]
+    fn level_rejects_unknown_label() {
+        expect_entry_error(
+            r#"(world: (width: 4, height: 4), hive: (x: 0, y: 0),
+                units: [(x: 0, y: 1, program: [Wait(1), Jump("nowhere")])])"#,
+            "units[0] program[1]",
+        );
+    }
+
+    #[test]
+    fn level_rejects_huge_world() {
+        expect_entry_error(
+            "(world: (width: 4294967295, height: 4294967295), hive: (x: 0, y: 0))",
+            "world",
+        );
+        expect_entry_error(
+            "(world: (width: 2048, height: 1024), hive: (x: 0, y: 0))",
+            "world",
+        );
+    }
+
+    #[test]
+    fn level_reports_parse_errors() {
+        match from_str("(world: (width: 4), hive: (x: 0, y: 0))") {
+            Err(LevelError::Parse(_)) => (),
+            _ => panic!("expected a parse error"),
+        }
+    }
+}
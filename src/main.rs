@@ -1,86 +1,51 @@
+mod assembly;
+mod asteroids;
+mod autopilot;
+#[cfg(feature = "gui")]
 mod circles;
 mod collision;
+#[cfg(feature = "gui")]
+mod console;
 mod entity_manager;
+mod events;
 mod game_state;
+mod headless;
+mod level;
+mod pathfinding;
+mod resource;
+mod scripting;
+mod snapshot;
 mod utils;
+mod vec2;
+#[cfg(feature = "gui")]
 mod widget;
-mod console;
 
-use sdl2::surface::Surface;
-use sdl2::ttf::Font;
+#[cfg(feature = "gui")]
 use sdl2::video::Window;
-use sdl2::render::Texture;
+#[cfg(feature = "gui")]
 use sdl2::render::Canvas;
+#[cfg(feature = "gui")]
 use std::path::PathBuf;
-use sdl2::pixels::Color;
+#[cfg(feature = "gui")]
 use std::borrow::BorrowMut;
 use rlua::Lua;
-use std::collections::HashMap;
 use std::io;
 use std::io::BufRead;
-use std::time::Instant;
-use std::{thread, time};
 
-use sdl2;
+#[cfg(feature = "gui")]
 use sdl2::event::Event;
 // use sdl2::EventPump;
 
+#[cfg(feature = "gui")]
 use crate::console::Console;
 
+#[cfg(feature = "gui")]
 use sdl2::keyboard::Keycode;
 //use sdl2::render::{Canvas, Texture, TextureCreator};
 //use sdl2::video::{Window, WindowContext};
 
-use entity_manager::Entity;
-use game_state::{Command, Position, UserCommand};
-use utils::Path;
-
 // todo: create gui implementation if a user wanted to play the game themselves.
 
-fn strip_empties(x: &mut HashMap<Entity, Entity>, value: &Entity) {
-    let tmp = x.clone();
-    let empties = tmp.iter().filter(|&(_, &v)| v.0 == value.0).map(|(k, _)| k);
-
-    for k in empties {
-        x.remove(k);
-    }
-}
-
-#[allow(dead_code)]
-fn generate_pathing_program(path: &Path) -> Vec<Command> {
-    let mut program = Vec::<Command>::new();
-
-    let _pos_offset_dist: f32 = 1.0;
-    let _speed = 0.5; // meters per second
-    let _tile_width = 16;
-
-    for p in path.path_points.iter() {
-        let current_pos = Position::new(p.0, p.1);
-        program.push(Command::MoveP(current_pos));
-    }
-
-    return program;
-}
-
-fn program_harvest_unit(
-    entity: &Entity,
-    target_entity: &Entity,
-    target_pos: &Position,
-) -> Vec<Command> {
-    // should be like get programable units.
-    let mut prog = Vec::new();
-    prog.push(Command::MoveD(Position::new(
-        target_pos.get_x(),
-        target_pos.get_y(),
-    )));
-    prog.push(Command::Harvest(target_entity.clone()));
-    prog.push(Command::MoveD(Position::new(0, 0)));
-
-    // entity 1 is hive.
-    prog.push(Command::Deposit(Entity(1)));
-    return prog;
-}
-
 fn lua_entry() -> rlua::Result<()> {
     let lua = Lua::new();
     lua.context(|lua_ctx| {
@@ -181,6 +146,57 @@ fn main() -> () {
     // lua_entry();
     // return;
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match asteroids::parse_args(&args) {
+        Ok(Some(options)) => {
+            #[cfg(feature = "gui")]
+            let result = asteroids::play(&options);
+            #[cfg(not(feature = "gui"))]
+            let result: Result<(), String> = {
+                let _ = options;
+                Err("the asteroids game needs the gui feature".to_string())
+            };
+            if let Err(e) = result {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => (),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+
+    let headless_options = match headless::parse_args(&args) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    // without a display the simulation is all there is to run.
+    #[cfg(not(feature = "gui"))]
+    let headless_options = Some(headless_options.unwrap_or_default());
+
+    match headless_options {
+        Some(options) => {
+            if let Err(e) = headless::run(&options) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        None => {
+            #[cfg(feature = "gui")]
+            gui_main();
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+fn gui_main() -> () {
     let sdl_context = sdl2::init().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
@@ -190,7 +206,7 @@ fn main() -> () {
         .build()
         .unwrap();
 
-    let mut canvas = window
+    let mut canvas: Canvas<Window> = window
         .into_canvas()
         .target_texture()
         .present_vsync()
@@ -198,21 +214,15 @@ fn main() -> () {
         .unwrap();
     canvas.clear();
 
-    let mut current_state = game_state::game_load();
-    let mut game_input = game_state::GameInput::default();
-
-    let frame_per_second_target = 60;
-    let milliseconds_per_frame = 1000.0 / frame_per_second_target as f32;
+    // let mut current_state = game_state::game_load();
+    // let mut game_input = game_state::GameInput::default();
+    // let mut autopilot = autopilot::Autopilot::new();
 
-    // each programed unit gets a target
-    // first entity is the actor, second is the "target"
-    // let mut programed_units = Vec::<(Entity, Entity)>::new();
-    let mut programmed_units = HashMap::<Entity, Entity>::new();
+    // let frame_per_second_target = 60;
+    // let milliseconds_per_frame = 1000.0 / frame_per_second_target as f32;
 
-    let mut frame = 0;
-    let max_frame = 20000;
-
-    let mut current_target_entity: Option<Entity> = None;
+    // let mut frame = 0;
+    // let max_frame = 20000;
 
     // 'running: while frame < max_frame {
     //     for event in event_pump.poll_iter() {
@@ -230,72 +240,11 @@ fn main() -> () {
 
     //     // User code section for controls purposes.
     //     // set values get user input.
-
-    //     if frame == 2 {
-    //         game_input.create_unit = true;
-    //     }
-
-    //     // if the current target entity runes out of mins set it to none so we can find a new target.
-    //     match current_target_entity {
-    //         Some(current_ent) => match current_state.get_mineable_count(&current_ent) {
-    //             None => {
-    //                 current_target_entity = None;
-    //             }
-    //             Some(amount) => {
-    //                 if amount == 0 {
-    //                     strip_empties(&mut programmed_units, &current_ent);
-    //                     current_target_entity = None;
-
-    //                     println!("Mine is empty");
-    //                 }
-    //             }
-    //         },
-    //         None => {}
-    //     }
-
-    //     // no current target.
-    //     if !current_target_entity.is_some() {
-    //         for e in current_state.get_mineable_nodes() {
-    //             // don't consider hive.
-    //             if *e != Entity(1) {
-    //                 match current_state.get_mineable_count(e) {
-    //                     Some(amount) => {
-    //                         if amount > 0 {
-    //                             println!("Setting Entity to: {}", e.0);
-    //                             current_target_entity = Some(*e);
-    //                             break;
-    //                         }
-    //                     }
-    //                     None => {}
-    //                 }
-    //             }
-    //         }
-    //     }
-
-    //     if current_target_entity.is_some() {
-    //         let mine_pos = current_state.get_entity_position(&current_target_entity.unwrap());
-
-    //         for e in current_state.get_programable_units() {
-    //             if !programmed_units.contains_key(e) {
-    //                 println!(
-    //                     "Setting actor {} target to: {}",
-    //                     e.0,
-    //                     current_target_entity.unwrap().0
-    //                 );
-    //                 let prog = program_harvest_unit(e, &current_target_entity.unwrap(), &mine_pos);
-
-    //                 game_input
-    //                     .user_commands
-    //                     .push(UserCommand::LoadProgram(*e, prog));
-
-    //                 programmed_units.insert(*e, current_target_entity.unwrap().clone());
-    //             }
-    //         }
-    //     }
+    //     autopilot.update(&current_state, frame, &mut game_input);
 
     //     // game input is finished perform server updating and such.
     //     let start = Instant::now();
-    //     current_state = game_state::game_update(current_state, 0.1, &game_input);
+    //     current_state = game_state::game_update(current_state, 0.1, &game_input).state;
     //     game_state::game_sdl2_render(&current_state, &mut canvas);
     //     // how expensive is this?
     //     canvas.present();
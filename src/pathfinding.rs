@@ -0,0 +1,490 @@
+#![allow(dead_code)]
+
+//! A* paths over the world grid.
+//!
+//! A Grid marks which tiles can't be walked on, either set by hand or taken
+//! from the colliding entities of a GameState. Paths come back as
+//! `utils::Path`, which `autopilot::generate_pathing_program` turns into
+//! MoveP commands. Like `utils::generate_path` the start tile is left out
+//! and the goal tile is the last point. Hex worlds use `Connectivity::Hex`.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+
+use crate::entity_manager::Entity;
+use crate::game_state::{GameState, GridLayout};
+use crate::utils::{hex_distance, hex_neighbours, Path};
+
+// cost of a straight and a diagonal step, diagonal is roughly straight * sqrt(2).
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// which neighbouring tiles a step can go to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// up, down, left and right.
+    Four,
+    /// Four plus the diagonals. diagonal steps can't cut the corner of a blocked tile.
+    Eight,
+    /// the 6 tiles around a tile of a GridLayout::Hex world.
+    Hex,
+}
+
+impl Connectivity {
+    /// plain steps between neighbouring tiles of the layout.
+    pub fn for_layout(layout: GridLayout) -> Self {
+        match layout {
+            GridLayout::Square => Connectivity::Four,
+            GridLayout::Hex => Connectivity::Hex,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathError {
+    /// start or goal is outside of the grid.
+    OutOfBounds { x: u32, y: u32 },
+    /// every way to the goal is blocked, or the goal itself is.
+    NoPath,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::OutOfBounds { x, y } => write!(f, "{}, {} is outside the world", x, y),
+            PathError::NoPath => write!(f, "no path"),
+        }
+    }
+}
+
+/// walkable area, every tile in the bounds is free unless blocked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub width: u32,
+    pub height: u32,
+    blocked: HashSet<(u32, u32)>,
+    // step cost multiplier of tiles that cost more than 1 to enter.
+    costs: HashMap<(u32, u32), u32>,
+}
+
+impl Grid {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width,
+            height: height,
+            blocked: HashSet::new(),
+            costs: HashMap::new(),
+        }
+    }
+
+    /// grid of the world's tile map with unwalkable tiles and the tile of every colliding
+    /// entity blocked, terrain costs carry over.
+    /// ignored entities, such as the unit that walks the path, don't block their tile.
+    pub fn from_game_state(game_state: &GameState, ignore: &[Entity]) -> Self {
+        let tile_map = game_state.get_tile_map();
+        let bounds = tile_map.bounds();
+        let mut grid = Self::new(bounds.width, bounds.height);
+        for ((x, y), tile) in tile_map.changed_tiles() {
+            if !tile.walkable {
+                grid.block(x, y);
+            }
+            grid.set_cost(x, y, tile.terrain_cost);
+        }
+        for (entity, collision, p) in game_state.collision.join(&game_state.positions) {
+            if collision.value && !ignore.contains(entity) {
+                grid.block(p.get_x(), p.get_y());
+            }
+        }
+        return grid;
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn block(&mut self, x: u32, y: u32) {
+        self.blocked.insert((x, y));
+    }
+
+    pub fn unblock(&mut self, x: u32, y: u32) {
+        self.blocked.remove(&(x, y));
+    }
+
+    pub fn is_blocked(&self, x: u32, y: u32) -> bool {
+        self.blocked.contains(&(x, y))
+    }
+
+    /// makes entering the tile cost times as much, costs below 1 count as 1.
+    pub fn set_cost(&mut self, x: u32, y: u32, cost: u32) {
+        if cost > 1 {
+            self.costs.insert((x, y), cost);
+        } else {
+            self.costs.remove(&(x, y));
+        }
+    }
+
+    pub fn cost(&self, x: u32, y: u32) -> u32 {
+        *self.costs.get(&(x, y)).unwrap_or(&1)
+    }
+
+    fn is_free(&self, x: i64, y: i64) -> bool {
+        x >= 0
+            && y >= 0
+            && self.contains(x as u32, y as u32)
+            && !self.is_blocked(x as u32, y as u32)
+    }
+
+    /// free tiles one step away from tile, with the cost of the step.
+    fn neighbours(&self, tile: (u32, u32), connectivity: Connectivity) -> Vec<((u32, u32), u32)> {
+        if connectivity == Connectivity::Hex {
+            return hex_neighbours(tile.0, tile.1)
+                .into_iter()
+                .filter(|(x, y)| self.is_free(*x as i64, *y as i64))
+                .map(|t| (t, STRAIGHT_COST.saturating_mul(self.cost(t.0, t.1))))
+                .collect();
+        }
+
+        let (x, y) = (tile.0 as i64, tile.1 as i64);
+        let mut res = Vec::new();
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            if self.is_free(x + dx, y + dy) {
+                let next = ((x + dx) as u32, (y + dy) as u32);
+                res.push((
+                    next,
+                    STRAIGHT_COST.saturating_mul(self.cost(next.0, next.1)),
+                ));
+            }
+        }
+        if connectivity == Connectivity::Eight {
+            for (dx, dy) in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+                if self.is_free(x + dx, y + dy)
+                    && self.is_free(x + dx, y)
+                    && self.is_free(x, y + dy)
+                {
+                    let next = ((x + dx) as u32, (y + dy) as u32);
+                    res.push((
+                        next,
+                        DIAGONAL_COST.saturating_mul(self.cost(next.0, next.1)),
+                    ));
+                }
+            }
+        }
+        return res;
+    }
+}
+
+// lower bound of the cost from a to b, keeps A* from overestimating.
+fn heuristic(a: (u32, u32), b: (u32, u32), connectivity: Connectivity) -> u32 {
+    let x_dist = a.0.abs_diff(b.0);
+    let y_dist = a.1.abs_diff(b.1);
+    match connectivity {
+        Connectivity::Four => (x_dist + y_dist) * STRAIGHT_COST,
+        Connectivity::Eight => {
+            let diagonal = x_dist.min(y_dist);
+            let straight = x_dist.max(y_dist) - diagonal;
+            diagonal * DIAGONAL_COST + straight * STRAIGHT_COST
+        }
+        Connectivity::Hex => hex_distance(a.0, a.1, b.0, b.1) * STRAIGHT_COST,
+    }
+}
+
+/// shortest path from start to goal around the blocked tiles of grid.
+/// the start tile itself may be blocked, the goal tile may not.
+pub fn find_path(
+    grid: &Grid,
+    start: (u32, u32),
+    goal: (u32, u32),
+    connectivity: Connectivity,
+) -> Result<Path, PathError> {
+    for (x, y) in [start, goal].iter() {
+        if !grid.contains(*x, *y) {
+            return Err(PathError::OutOfBounds { x: *x, y: *y });
+        }
+    }
+    if start == goal {
+        return Ok(Path::new());
+    }
+    if grid.is_blocked(goal.0, goal.1) {
+        return Err(PathError::NoPath);
+    }
+
+    // tiles are only expanded once, so the search ends after at most width * height steps.
+    let mut open = BinaryHeap::new();
+    let mut cost_so_far: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut came_from: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+    let mut closed: HashSet<(u32, u32)> = HashSet::new();
+
+    cost_so_far.insert(start, 0);
+    open.push(Reverse((heuristic(start, goal, connectivity), start)));
+
+    while let Some(Reverse((_, tile))) = open.pop() {
+        if tile == goal {
+            let mut r_path = Path::new();
+            let mut current = goal;
+            while current != start {
+                r_path.path_points.push(current);
+                current = came_from[&current];
+            }
+            r_path.path_points.reverse();
+            return Ok(r_path);
+        }
+        if !closed.insert(tile) {
+            continue;
+        }
+
+        let cost = cost_so_far[&tile];
+        for (next, step_cost) in grid.neighbours(tile, connectivity) {
+            let next_cost = cost.saturating_add(step_cost);
+            if cost_so_far.get(&next).map_or(true, |c| next_cost < *c) {
+                cost_so_far.insert(next, next_cost);
+                came_from.insert(next, tile);
+                open.push(Reverse((
+                    next_cost.saturating_add(heuristic(next, goal, connectivity)),
+                    next,
+                )));
+            }
+        }
+    }
+    return Err(PathError::NoPath);
+}
+
+// paths found so far, keyed by start and goal.
+type PathCache = HashMap<((u32, u32), (u32, u32)), Result<Path, PathError>>;
+
+/// finds paths on a grid and remembers them, changing the grid forgets them.
+#[derive(Debug, Clone)]
+pub struct Pathfinder {
+    grid: Grid,
+    connectivity: Connectivity,
+    cache: PathCache,
+}
+
+impl Pathfinder {
+    pub fn new(grid: Grid, connectivity: Connectivity) -> Self {
+        Self {
+            grid: grid,
+            connectivity: connectivity,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// replaces the grid, for example with a fresh Grid::from_game_state.
+    pub fn set_grid(&mut self, grid: Grid) {
+        if grid != self.grid {
+            self.grid = grid;
+            self.cache.clear();
+        }
+    }
+
+    pub fn block(&mut self, x: u32, y: u32) {
+        if !self.grid.is_blocked(x, y) {
+            self.grid.block(x, y);
+            self.cache.clear();
+        }
+    }
+
+    pub fn unblock(&mut self, x: u32, y: u32) {
+        if self.grid.is_blocked(x, y) {
+            self.grid.unblock(x, y);
+            self.cache.clear();
+        }
+    }
+
+    pub fn cached_paths(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn find(&mut self, start: (u32, u32), goal: (u32, u32)) -> Result<Path, PathError> {
+        let grid = &self.grid;
+        let connectivity = self.connectivity;
+        self.cache
+            .entry((start, goal))
+            .or_insert_with(|| find_path(grid, start, goal, connectivity))
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::autopilot::generate_pathing_program;
+    use crate::game_state::{game_update, spawn_unit, Command, GameInput, Position};
+    use crate::utils::manhat_distance;
+
+    // wall along x = 2 from y = 0 to y = 3, the way around is at y = 4.
+    fn walled_grid() -> Grid {
+        let mut grid = Grid::new(5, 5);
+        for y in 0..4 {
+            grid.block(2, y);
+        }
+        grid
+    }
+
+    fn check_steps(grid: &Grid, start: (u32, u32), path: &Path, connectivity: Connectivity) {
+        let mut current = start;
+        for p in path.path_points.iter() {
+            assert!(!grid.is_blocked(p.0, p.1), "{:?} is blocked", p);
+            let x_dist = (p.0 as i64 - current.0 as i64).abs();
+            let y_dist = (p.1 as i64 - current.1 as i64).abs();
+            match connectivity {
+                Connectivity::Four => assert_eq!(x_dist + y_dist, 1),
+                Connectivity::Eight => assert_eq!(x_dist.max(y_dist), 1),
+                Connectivity::Hex => assert_eq!(hex_distance(current.0, current.1, p.0, p.1), 1),
+            }
+            current = *p;
+        }
+    }
+
+    #[test]
+    fn path_around_wall() {
+        let grid = walled_grid();
+        let path = find_path(&grid, (0, 0), (4, 0), Connectivity::Four).unwrap();
+        check_steps(&grid, (0, 0), &path, Connectivity::Four);
+        assert_eq!(path.path_points.last(), Some(&(4, 0)));
+        // down to y = 4, across and back up.
+        assert_eq!(path.path_points.len(), 12);
+    }
+
+    #[test]
+    fn eight_connectivity_is_shorter() {
+        let grid = walled_grid();
+        let path = find_path(&grid, (0, 0), (4, 0), Connectivity::Eight).unwrap();
+        check_steps(&grid, (0, 0), &path, Connectivity::Eight);
+        assert_eq!(path.path_points.last(), Some(&(4, 0)));
+        assert!(path.path_points.len() < 12);
+
+        let open = Grid::new(5, 5);
+        let path = find_path(&open, (0, 0), (3, 3), Connectivity::Eight).unwrap();
+        assert_eq!(path.path_points, vec![(1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn hex_path_around_wall() {
+        let grid = walled_grid();
+        let path = find_path(&grid, (0, 0), (4, 0), Connectivity::Hex).unwrap();
+        check_steps(&grid, (0, 0), &path, Connectivity::Hex);
+        assert_eq!(path.path_points.last(), Some(&(4, 0)));
+
+        let open = Grid::new(5, 5);
+        let path = find_path(&open, (0, 0), (0, 4), Connectivity::Hex).unwrap();
+        assert_eq!(path.path_points.len(), hex_distance(0, 0, 0, 4) as usize);
+    }
+
+    #[test]
+    fn no_corner_cutting() {
+        let mut grid = Grid::new(2, 2);
+        grid.block(1, 0);
+        let path = find_path(&grid, (0, 0), (1, 1), Connectivity::Eight).unwrap();
+        assert_eq!(path.path_points, vec![(0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn no_path() {
+        let mut grid = walled_grid();
+        grid.block(2, 4);
+        assert_eq!(
+            find_path(&grid, (0, 0), (4, 0), Connectivity::Eight),
+            Err(PathError::NoPath)
+        );
+        assert_eq!(
+            find_path(&grid, (0, 0), (2, 1), Connectivity::Four),
+            Err(PathError::NoPath)
+        );
+        assert_eq!(
+            find_path(&grid, (0, 0), (7, 0), Connectivity::Four),
+            Err(PathError::OutOfBounds { x: 7, y: 0 })
+        );
+        assert_eq!(
+            find_path(&grid, (0, 0), (0, 0), Connectivity::Four),
+            Ok(Path::new())
+        );
+    }
+
+    #[test]
+    fn pathfinder_caches_until_grid_changes() {
+        let mut pathfinder = Pathfinder::new(Grid::new(5, 5), Connectivity::Four);
+        let direct = pathfinder.find((0, 0), (4, 0)).unwrap();
+        assert_eq!(direct.path_points.len(), 4);
+        pathfinder.find((0, 0), (4, 0)).unwrap();
+        assert_eq!(pathfinder.cached_paths(), 1);
+
+        pathfinder.block(2, 0);
+        assert_eq!(pathfinder.cached_paths(), 0);
+        let around = pathfinder.find((0, 0), (4, 0)).unwrap();
+        assert_eq!(around.path_points.len(), 6);
+    }
+
+    #[test]
+    fn grid_from_game_state() {
+        let mut game_state = GameState::new();
+        game_state.create_hive(0, 0);
+        let hive = game_state.get_hive().unwrap();
+
+        let grid = Grid::from_game_state(&game_state, &[]);
+        assert!(grid.is_blocked(0, 0));
+        let grid = Grid::from_game_state(&game_state, &[hive]);
+        assert!(!grid.is_blocked(0, 0));
+
+        game_state.tile_map.get_mut(3, 3).unwrap().walkable = false;
+        game_state.tile_map.get_mut(4, 4).unwrap().terrain_cost = 5;
+        let grid = Grid::from_game_state(&game_state, &[]);
+        assert!(grid.is_blocked(3, 3));
+        assert_eq!(grid.cost(4, 4), 5);
+        assert_eq!(grid.cost(4, 5), 1);
+    }
+
+    #[test]
+    fn path_avoids_rough_terrain() {
+        let mut grid = Grid::new(3, 3);
+        grid.set_cost(1, 0, 5);
+        let path = find_path(&grid, (0, 0), (2, 0), Connectivity::Four).unwrap();
+        // two steps through the rough tile cost more than going around it.
+        assert_eq!(path.path_points, vec![(0, 1), (1, 1), (2, 1), (2, 0)]);
+
+        grid.set_cost(1, 0, 2);
+        let path = find_path(&grid, (0, 0), (2, 0), Connectivity::Four).unwrap();
+        assert_eq!(path.path_points, vec![(1, 0), (2, 0)]);
+
+        // huge costs saturate instead of overflowing.
+        let mut grid = Grid::new(3, 1);
+        grid.set_cost(1, 0, u32::MAX);
+        grid.set_cost(2, 0, u32::MAX);
+        for connectivity in [Connectivity::Four, Connectivity::Eight, Connectivity::Hex].iter() {
+            let path = find_path(&grid, (0, 0), (2, 0), *connectivity).unwrap();
+            assert_eq!(path.path_points.last(), Some(&(2, 0)));
+        }
+    }
+
+    #[test]
+    fn unit_walks_path_program() {
+        let mut game_state = GameState::new();
+        game_state.create_hive(0, 0);
+        let unit = spawn_unit(&mut game_state, Position::new(0, 1));
+        let hive = game_state.get_hive().unwrap();
+
+        let grid = Grid::from_game_state(&game_state, &[unit]);
+        let path = find_path(&grid, (0, 1), (2, 0), Connectivity::Eight).unwrap();
+        let program = generate_pathing_program(&path);
+        assert_eq!(program.len(), path.path_points.len());
+        assert_eq!(program[0], Command::MoveP(Position::new(1, 1)));
+        game_state.memory.get_mut(&unit).unwrap().commands = program;
+
+        for _ in 0..path.path_points.len() {
+            let update = game_update(game_state, 0.1, &GameInput::default());
+            assert!(update.events.is_empty(), "{:?}", update.events);
+            game_state = update.state;
+        }
+        let end = game_state.get_entity_position(&unit);
+        assert_eq!((end.get_x(), end.get_y()), (2, 0));
+        let hive_pos = game_state.get_entity_position(&hive);
+        assert_eq!(
+            manhat_distance(end.get_x(), end.get_y(), hive_pos.get_x(), hive_pos.get_y()),
+            2
+        );
+    }
+}
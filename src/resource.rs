@@ -0,0 +1,58 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// kinds of solids that can be mined, carried and stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Resource {
+    Iron,
+    Copper,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 2] = [Resource::Iron, Resource::Copper];
+
+    /// name used by level files, programs and scripts.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resource::Iron => "iron",
+            Resource::Copper => "copper",
+        }
+    }
+
+    /// load a single unit of the resource puts on a carrier.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Resource::Iron => 1,
+            Resource::Copper => 2,
+        }
+    }
+
+    /// inverse of name, case insensitive.
+    pub fn from_name(name: &str) -> Option<Resource> {
+        Resource::ALL
+            .iter()
+            .find(|r| r.name().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_names_round_trip() {
+        for r in Resource::ALL.iter() {
+            assert_eq!(Resource::from_name(r.name()), Some(*r));
+        }
+        assert_eq!(Resource::from_name("COPPER"), Some(Resource::Copper));
+        assert_eq!(Resource::from_name("gold"), None);
+    }
+}
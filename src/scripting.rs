@@ -0,0 +1,735 @@
+//! Lua scripting bridge for controlling the hive.
+//!
+//! A control script defines a global `on_tick(tick)` function which is called
+//! once per frame before game_update. Inside it the global `game` table offers
+//! read only queries on the current game state and functions that queue user
+//! commands for the frame:
+//!
+//! | function                          | returns / effect                               |
+//! |-----------------------------------|------------------------------------------------|
+//! | `game.units()`                    | list of every entity id                        |
+//! | `game.programmable_units()`       | list of entity ids that have memory            |
+//! | `game.mineable_nodes()`           | list of entity ids holding resources           |
+//! | `game.hive()`                     | hive entity id or nil                          |
+//! | `game.position(id)`               | x, y tile of the entity or nil                 |
+//! | `game.mineable_count(id)`         | resources left in the entity or nil            |
+//! | `game.inventory(id)`              | `{ iron = n, copper = n }` or nil              |
+//! | `game.hive_inventory()`           | `game.inventory(game.hive())`                  |
+//! | `game.spawn_unit()`               | asks the hive for a new unit this frame        |
+//! | `game.load_program(id, commands)` | queues `UserCommand::LoadProgram`              |
+//! | `game.load_command(id, command)`  | queues `UserCommand::LoadCommand`              |
+//! | `game.load_script(id, source)`    | queues `UserCommand::LoadScript`               |
+//!
+//! Commands are tables whose first element names the command:
+//! `{"move_p", x, y}`, `{"move_d", x, y}`, `{"harvest", node, resource}`,
+//! `{"deposit", node}`,
+//! `{"wait", ticks}`, `{"halt"}`, `{"label", name}`, `{"jump", label}` and
+//! `{"jump_if", condition, label}` where condition is one of `"cargo_full"`,
+//! `"cargo_empty"`, `"energy_low"` or `{"mine_empty", node}`.
+//!
+//! Query functions only exist while on_tick runs, keeping references to them
+//! for later frames is an error.
+//!
+//! Units can also carry their own program as a lua script (see
+//! `UserCommand::LoadScript`). The script runs as a coroutine managed by
+//! [`UnitScripts`]: whenever the unit has finished its last command the
+//! coroutine is resumed with a status table for the unit
+//! (`{ id, x, y, iron, copper, load, max_load, energy, hive }`) and runs until it yields the next
+//! command. The helpers `move_p(x, y)`, `move_d(x, y)`, `harvest(node, resource)` and
+//! `deposit(node)` yield the command and return the status table once it is
+//! done, so a harvesting loop reads:
+//!
+//! ```lua
+//! local me = ...
+//! while me.iron < 5 do
+//!     move_d(10, 5)
+//!     me = harvest(2, "iron")
+//! end
+//! move_d(0, 0)
+//! deposit(me.hive)
+//! ```
+//!
+//! Yielding nothing idles the unit for a tick, returning from the script
+//! leaves the unit idle for good. Unit scripts use lua for their control flow,
+//! yielding `halt`, `label`, `jump` or `jump_if` stops the script with an error. Each resume may only run a limited number of
+//! lua instructions, scripts going over the budget are stopped.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use rlua::{Context, HookTriggers, Lua, RegistryKey, Table, Thread, ThreadStatus};
+
+use crate::entity_manager::Entity;
+use crate::game_state::{Command, Condition, GameInput, GameState, Position, UserCommand};
+use crate::resource::Resource;
+
+fn condition_from_value(value: rlua::Value) -> rlua::Result<Condition> {
+    let (name, node): (String, Option<u64>) = match value {
+        rlua::Value::Table(t) => (t.get(1)?, t.get(2)?),
+        rlua::Value::String(s) => (s.to_str()?.to_string(), None),
+        _ => {
+            return Err(rlua::Error::RuntimeError(
+                "condition must be a string or table".to_string(),
+            ))
+        }
+    };
+    match (name.as_str(), node) {
+        ("cargo_full", _) => Ok(Condition::CargoFull),
+        ("cargo_empty", _) => Ok(Condition::CargoEmpty),
+        ("energy_low", _) => Ok(Condition::EnergyLow),
+        ("mine_empty", Some(node)) => Ok(Condition::MineEmpty(Entity(node))),
+        _ => Err(rlua::Error::RuntimeError(format!(
+            "unknown condition \"{}\"",
+            name
+        ))),
+    }
+}
+
+/// converts a command table (see module docs) into a Command.
+pub fn command_from_table(table: Table) -> rlua::Result<Command> {
+    let name: String = table.get(1)?;
+    match name.as_str() {
+        "move_p" => Ok(Command::MoveP(Position::new(table.get(2)?, table.get(3)?))),
+        "move_d" => Ok(Command::MoveD(Position::new(table.get(2)?, table.get(3)?))),
+        "harvest" => {
+            let resource: String = table.get(3)?;
+            match Resource::from_name(&resource) {
+                Some(r) => Ok(Command::Harvest(Entity(table.get(2)?), r)),
+                None => Err(rlua::Error::RuntimeError(format!(
+                    "unknown resource \"{}\"",
+                    resource
+                ))),
+            }
+        }
+        "deposit" => Ok(Command::Deposit(Entity(table.get(2)?))),
+        "wait" => Ok(Command::Wait(table.get(2)?)),
+        "halt" => Ok(Command::Halt),
+        "label" => Ok(Command::Label(table.get(2)?)),
+        "jump" => Ok(Command::Jump(table.get(2)?)),
+        "jump_if" => Ok(Command::JumpIf(
+            condition_from_value(table.get(2)?)?,
+            table.get(3)?,
+        )),
+        _ => Err(rlua::Error::RuntimeError(format!(
+            "unknown command \"{}\"",
+            name
+        ))),
+    }
+}
+
+fn sorted_ids(entities: Vec<&Entity>) -> Vec<u64> {
+    let mut ids: Vec<u64> = entities.iter().map(|e| e.0).collect();
+    ids.sort();
+    ids
+}
+
+/// resource amounts held by the entity, keyed by resource name.
+fn inventory_table<'lua>(
+    ctx: Context<'lua>,
+    game_state: &GameState,
+    entity: &Entity,
+) -> rlua::Result<Option<Table<'lua>>> {
+    match game_state.solid_containers.get(entity) {
+        Some(container) => {
+            let inventory = ctx.create_table()?;
+            for r in Resource::ALL.iter() {
+                inventory.set(r.name(), container.get(*r))?;
+            }
+            Ok(Some(inventory))
+        }
+        None => Ok(None),
+    }
+}
+
+/// the `game` table handed to scripts, queries read from game_state and
+/// commands are collected into game_input.
+fn create_game_table<'lua, 'scope>(
+    ctx: Context<'lua>,
+    scope: &rlua::Scope<'lua, 'scope>,
+    game_state: &'scope GameState,
+    game_input: &'scope RefCell<&mut GameInput>,
+) -> rlua::Result<Table<'lua>> {
+    let game = ctx.create_table()?;
+
+    game.set(
+        "units",
+        scope.create_function(move |_, ()| Ok(sorted_ids(game_state.get_units())))?,
+    )?;
+    game.set(
+        "programmable_units",
+        scope.create_function(move |_, ()| Ok(sorted_ids(game_state.get_programable_units())))?,
+    )?;
+    game.set(
+        "mineable_nodes",
+        scope.create_function(move |_, ()| Ok(sorted_ids(game_state.get_mineable_nodes())))?,
+    )?;
+    game.set(
+        "hive",
+        scope.create_function(move |_, ()| Ok(game_state.get_hive().map(|e| e.0)))?,
+    )?;
+    game.set(
+        "position",
+        scope.create_function(move |_, id: u64| {
+            Ok(match game_state.positions.get(&Entity(id)) {
+                Some(p) => (Some(p.get_x()), Some(p.get_y())),
+                None => (None, None),
+            })
+        })?,
+    )?;
+    game.set(
+        "mineable_count",
+        scope.create_function(move |_, id: u64| Ok(game_state.get_mineable_count(&Entity(id))))?,
+    )?;
+    game.set(
+        "inventory",
+        scope.create_function(move |ctx, id: u64| inventory_table(ctx, game_state, &Entity(id)))?,
+    )?;
+    game.set(
+        "hive_inventory",
+        scope.create_function(move |ctx, ()| match game_state.get_hive() {
+            Some(hive) => inventory_table(ctx, game_state, &hive),
+            None => Ok(None),
+        })?,
+    )?;
+
+    game.set(
+        "spawn_unit",
+        scope.create_function(move |_, ()| {
+            game_input.borrow_mut().create_unit = true;
+            Ok(())
+        })?,
+    )?;
+    game.set(
+        "load_program",
+        scope.create_function(move |_, (id, commands): (u64, Vec<Table>)| {
+            let mut program = Vec::new();
+            for c in commands {
+                program.push(command_from_table(c)?);
+            }
+            game_input
+                .borrow_mut()
+                .user_commands
+                .push(UserCommand::LoadProgram(Entity(id), program));
+            Ok(())
+        })?,
+    )?;
+    game.set(
+        "load_command",
+        scope.create_function(move |_, (id, command): (u64, Table)| {
+            let command = command_from_table(command)?;
+            game_input
+                .borrow_mut()
+                .user_commands
+                .push(UserCommand::LoadCommand(Entity(id), command));
+            Ok(())
+        })?,
+    )?;
+    game.set(
+        "load_script",
+        scope.create_function(move |_, (id, source): (u64, String)| {
+            game_input
+                .borrow_mut()
+                .user_commands
+                .push(UserCommand::LoadScript(Entity(id), source));
+            Ok(())
+        })?,
+    )?;
+
+    Ok(game)
+}
+
+/// a loaded control script.
+pub struct ScriptEngine {
+    lua: Lua,
+}
+
+impl ScriptEngine {
+    /// runs the script source once, it should define on_tick.
+    pub fn new(source: &str) -> rlua::Result<Self> {
+        let lua = Lua::new();
+        lua.context(|ctx| -> rlua::Result<()> {
+            ctx.load(source).set_name("control script")?.exec()?;
+            let _: rlua::Function = ctx.globals().get("on_tick").map_err(|_| {
+                rlua::Error::RuntimeError("control script has no on_tick function".to_string())
+            })?;
+            Ok(())
+        })?;
+        Ok(Self { lua: lua })
+    }
+
+    /// calls the script's on_tick, anything it queues is added to game_input.
+    pub fn tick(
+        &self,
+        game_state: &GameState,
+        tick: u32,
+        game_input: &mut GameInput,
+    ) -> rlua::Result<()> {
+        let game_input = RefCell::new(game_input);
+        self.lua.context(|ctx| {
+            ctx.scope(|scope| {
+                let game = create_game_table(ctx, scope, game_state, &game_input)?;
+                ctx.globals().set("game", game)?;
+
+                let on_tick: rlua::Function = ctx.globals().get("on_tick")?;
+                let res = on_tick.call::<_, ()>(tick);
+
+                // don't leave dangling scope functions around for the next frame.
+                ctx.globals().set("game", rlua::Value::Nil)?;
+                res
+            })
+        })
+    }
+}
+
+/// lua instructions a unit script may run per resume.
+pub const DEFAULT_INSTRUCTION_BUDGET: u32 = 10_000;
+
+// how often the instruction hook fires, the budget is checked in steps of this.
+const HOOK_INTERVAL: u32 = 100;
+
+const UNIT_PRELUDE: &str = r#"
+function move_p(x, y) return coroutine.yield({"move_p", x, y}) end
+function move_d(x, y) return coroutine.yield({"move_d", x, y}) end
+function harvest(node, resource) return coroutine.yield({"harvest", node, resource}) end
+function deposit(node) return coroutine.yield({"deposit", node}) end
+"#;
+
+struct UnitScript {
+    // Memory::script_generation the coroutine was started for.
+    generation: u32,
+    // None if the script failed to load.
+    thread: Option<RegistryKey>,
+    // set once the script errored, it isn't resumed again until replaced.
+    failed: bool,
+}
+
+/// runs the lua programs of every unit whose memory holds a script.
+pub struct UnitScripts {
+    lua: Lua,
+    scripts: HashMap<Entity, UnitScript>,
+    instructions: Arc<AtomicU32>,
+}
+
+fn unit_status<'lua>(
+    ctx: Context<'lua>,
+    entity: &Entity,
+    game_state: &GameState,
+) -> rlua::Result<Table<'lua>> {
+    let status = ctx.create_table()?;
+    status.set("id", entity.0)?;
+    if let Some(p) = game_state.positions.get(entity) {
+        status.set("x", p.get_x())?;
+        status.set("y", p.get_y())?;
+    }
+    if let Some(c) = game_state.solid_containers.get(entity) {
+        for r in Resource::ALL.iter() {
+            status.set(r.name(), c.get(*r))?;
+        }
+        status.set("load", c.load())?;
+        status.set("max_load", c.max_load)?;
+    }
+    if let Some(e) = game_state.energy_levels.get(entity) {
+        status.set("energy", e.value)?;
+    }
+    status.set("hive", game_state.get_hive().map(|e| e.0))?;
+    Ok(status)
+}
+
+impl UnitScripts {
+    pub fn new(instruction_budget: u32) -> Self {
+        let lua = Lua::new();
+        let instructions = Arc::new(AtomicU32::new(0));
+
+        let counter = instructions.clone();
+        lua.set_hook(
+            HookTriggers {
+                every_nth_instruction: Some(HOOK_INTERVAL),
+                ..Default::default()
+            },
+            move |_, _| {
+                let used = counter.fetch_add(HOOK_INTERVAL, Ordering::Relaxed) + HOOK_INTERVAL;
+                if used > instruction_budget {
+                    Err(rlua::Error::RuntimeError(format!(
+                        "instruction budget of {} exceeded",
+                        instruction_budget
+                    )))
+                } else {
+                    Ok(())
+                }
+            },
+        );
+        lua.context(|ctx| ctx.load(UNIT_PRELUDE).exec())
+            .expect("unit script prelude failed to load");
+
+        Self {
+            lua: lua,
+            scripts: HashMap::new(),
+            instructions: instructions,
+        }
+    }
+
+    /// hands the next command to every scripted unit that finished its last one.
+    /// units whose script failed this tick are returned with the error, they
+    /// stay idle until a script is loaded again.
+    pub fn step(&mut self, game_state: &mut GameState) -> Vec<(Entity, rlua::Error)> {
+        let mut scripted: Vec<(Entity, String, u32)> = game_state
+            .memory
+            .iter()
+            .filter_map(|(e, m)| {
+                m.script
+                    .as_ref()
+                    .map(|s| (*e, s.clone(), m.script_generation))
+            })
+            .collect();
+        scripted.sort_by_key(|(e, _, _)| *e);
+
+        let UnitScripts {
+            lua,
+            scripts,
+            instructions,
+        } = self;
+
+        let mut errors = Vec::new();
+        lua.context(|ctx| {
+            // forget coroutines of units that had a script loaded since they started.
+            scripts.retain(|e, script| {
+                scripted
+                    .iter()
+                    .any(|(s_e, _, generation)| s_e == e && *generation == script.generation)
+            });
+
+            for (entity, source, generation) in scripted.iter() {
+                let memory = game_state.memory.get(entity).unwrap();
+                if !memory.commands.is_empty() {
+                    // still busy with the last command.
+                    continue;
+                }
+
+                if !scripts.contains_key(entity) {
+                    let thread = ctx
+                        .load(source)
+                        .set_name(&format!("unit {} script", entity.0))
+                        .and_then(|chunk| chunk.into_function())
+                        .and_then(|function| ctx.create_thread(function))
+                        .and_then(|thread| ctx.create_registry_value(thread));
+                    let failed = thread.is_err();
+                    let (thread, error) = match thread {
+                        Ok(t) => (Some(t), None),
+                        Err(e) => (None, Some(e)),
+                    };
+                    scripts.insert(
+                        *entity,
+                        UnitScript {
+                            generation: *generation,
+                            thread: thread,
+                            failed: failed,
+                        },
+                    );
+                    if let Some(e) = error {
+                        errors.push((*entity, e));
+                        continue;
+                    }
+                }
+
+                let script = scripts.get_mut(entity).unwrap();
+                if script.failed {
+                    continue;
+                }
+                let next = script
+                    .thread
+                    .as_ref()
+                    .ok_or(rlua::Error::RuntimeError("missing unit thread".to_string()))
+                    .and_then(|key| ctx.registry_value::<Thread>(key))
+                    .and_then(|thread| {
+                        if thread.status() != ThreadStatus::Resumable {
+                            // script returned, the unit is done.
+                            return Ok(None);
+                        }
+                        let status = unit_status(ctx, entity, game_state)?;
+                        instructions.store(0, Ordering::Relaxed);
+                        match thread.resume::<_, Option<Table>>(status)? {
+                            Some(t) => match command_from_table(t)? {
+                                // these never finish, the script would wait on them forever.
+                                Command::Halt
+                                | Command::Label(_)
+                                | Command::Jump(_)
+                                | Command::JumpIf(..) => Err(rlua::Error::RuntimeError(
+                                    "unit scripts can't yield halt, label or jumps".to_string(),
+                                )),
+                                command => Ok(Some(command)),
+                            },
+                            None => Ok(None),
+                        }
+                    });
+
+                match next {
+                    Ok(Some(command)) => {
+                        game_state
+                            .memory
+                            .get_mut(entity)
+                            .unwrap()
+                            .commands
+                            .push(command);
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        script.failed = true;
+                        errors.push((*entity, e));
+                    }
+                }
+            }
+
+            ctx.expire_registry_values();
+        });
+
+        return errors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{game_load, game_update};
+
+    #[test]
+    fn script_queries_state() {
+        let engine = ScriptEngine::new(
+            r#"
+            function on_tick(tick)
+                local x, y = game.position(2)
+                assert(x == 10 and y == 5)
+                assert(game.mineable_count(2) == 2)
+                assert(game.hive() == 1)
+                assert(game.hive_inventory().iron == 0)
+                assert(#game.units() == 3)
+                assert(#game.programmable_units() == 0)
+            end
+            "#,
+        )
+        .unwrap();
+
+        let mut game_input = GameInput::default();
+        engine.tick(&game_load(), 0, &mut game_input).unwrap();
+        assert_eq!(game_input.user_commands.len(), 0);
+    }
+
+    #[test]
+    fn script_queues_commands() {
+        let engine = ScriptEngine::new(
+            r#"
+            function on_tick(tick)
+                game.spawn_unit()
+                game.load_program(4, {{"move_d", 10, 5}, {"harvest", 2, "copper"},
+                                      {"jump_if", {"mine_empty", 2}, "x"}, {"label", "x"}})
+                game.load_command(4, {"deposit", 1})
+            end
+            "#,
+        )
+        .unwrap();
+
+        let mut game_input = GameInput::default();
+        engine.tick(&game_load(), 0, &mut game_input).unwrap();
+
+        assert!(game_input.create_unit);
+        assert_eq!(game_input.user_commands.len(), 2);
+        match &game_input.user_commands[0] {
+            UserCommand::LoadProgram(e, prog) => {
+                assert_eq!(*e, Entity(4));
+                assert_eq!(
+                    *prog,
+                    vec![
+                        Command::MoveD(Position::new(10, 5)),
+                        Command::Harvest(Entity(2), Resource::Copper),
+                        Command::JumpIf(Condition::MineEmpty(Entity(2)), "x".to_string()),
+                        Command::Label("x".to_string()),
+                    ]
+                );
+            }
+            _ => panic!("expected a program"),
+        }
+        match &game_input.user_commands[1] {
+            UserCommand::LoadCommand(e, command) => {
+                assert_eq!(*e, Entity(4));
+                assert_eq!(*command, Command::Deposit(Entity(1)));
+            }
+            _ => panic!("expected a command"),
+        }
+    }
+
+    #[test]
+    fn script_rejects_unknown_command() {
+        let engine = ScriptEngine::new(
+            r#"
+            function on_tick(tick)
+                game.load_command(4, {"teleport", 1})
+            end
+            "#,
+        )
+        .unwrap();
+
+        let mut game_input = GameInput::default();
+        assert!(engine.tick(&game_load(), 0, &mut game_input).is_err());
+    }
+
+    #[test]
+    fn script_needs_on_tick() {
+        assert!(ScriptEngine::new("x = 1").is_err());
+    }
+
+    #[test]
+    fn autopilot_script_harvests() {
+        let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("scripts/autopilot.lua");
+        let engine = ScriptEngine::new(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let mut game_state = game_load();
+        for tick in 0..10 {
+            let mut game_input = GameInput::default();
+            engine.tick(&game_state, tick, &mut game_input).unwrap();
+            game_state = game_update(game_state, 0.1, &game_input).state;
+        }
+
+        // unit spawned on tick 2 and sent to the small iron mine.
+        let units = game_state.get_programable_units();
+        assert_eq!(units.len(), 1);
+        let memory = game_state.memory.get(units[0]).unwrap();
+        assert_eq!(
+            memory.commands[1],
+            Command::Harvest(Entity(2), Resource::Iron)
+        );
+        assert_eq!(memory.commands[3], Command::Deposit(Entity(1)));
+    }
+
+    fn scripted_state(source: &str) -> GameState {
+        let mut game_state = crate::level::from_str(
+            r#"(
+                world: (width: 5, height: 5),
+                hive: (x: 0, y: 0),
+                nodes: [(name: "iron", x: 1, y: 1, resource: "iron", amount: 10)],
+                units: [(x: 0, y: 1)],
+            )"#,
+        )
+        .unwrap();
+        let mut game_input = GameInput::default();
+        game_input
+            .user_commands
+            .push(UserCommand::LoadScript(Entity(3), source.to_string()));
+        game_state = game_update(game_state, 0.1, &game_input).state;
+        game_state
+    }
+
+    #[test]
+    fn unit_script_feeds_commands() {
+        let mut game_state = scripted_state(
+            r#"
+            local me = ...
+            while me.iron < 3 do
+                me = harvest(2, "iron")
+            end
+            deposit(me.hive)
+            "#,
+        );
+        let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 0);
+        assert_eq!(
+            game_state.memory.get(&Entity(3)).unwrap().commands,
+            vec![Command::Harvest(Entity(2), Resource::Iron)]
+        );
+
+        for _ in 0..10 {
+            assert_eq!(unit_scripts.step(&mut game_state).len(), 0);
+            game_state = game_update(game_state, 0.1, &GameInput::default()).state;
+        }
+
+        // three harvests then a single deposit, after that the script is done.
+        assert_eq!(game_state.get_mineable_count(&Entity(2)), Some(7));
+        assert_eq!(game_state.get_mineable_count(&Entity(3)), Some(2));
+        assert_eq!(game_state.get_mineable_count(&Entity(1)), Some(1));
+        assert!(game_state
+            .memory
+            .get(&Entity(3))
+            .unwrap()
+            .commands
+            .is_empty());
+    }
+
+    #[test]
+    fn unit_script_budget_stops_runaway_script() {
+        let mut game_state = scripted_state("while true do end");
+        let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+
+        let errors = unit_scripts.step(&mut game_state);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, Entity(3));
+        assert!(game_state
+            .memory
+            .get(&Entity(3))
+            .unwrap()
+            .commands
+            .is_empty());
+
+        // a failed script isn't retried every tick.
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 0);
+    }
+
+    #[test]
+    fn reloading_a_script_restarts_it() {
+        let mut game_state = scripted_state("while true do end");
+        let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 1);
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 0);
+
+        // the same source loaded again runs from the start.
+        let mut game_input = GameInput::default();
+        game_input.user_commands.push(UserCommand::LoadScript(
+            Entity(3),
+            "while true do end".to_string(),
+        ));
+        game_state = game_update(game_state, 0.1, &game_input).state;
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 1);
+    }
+
+    #[test]
+    fn unit_script_rejects_control_flow() {
+        for source in [
+            "coroutine.yield({\"halt\"})",
+            "coroutine.yield({\"label\", \"top\"})",
+            "coroutine.yield({\"jump\", \"nowhere\"})",
+        ]
+        .iter()
+        {
+            let mut game_state = scripted_state(source);
+            let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+            assert_eq!(unit_scripts.step(&mut game_state).len(), 1, "{}", source);
+            assert!(game_state
+                .memory
+                .get(&Entity(3))
+                .unwrap()
+                .commands
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn unit_script_reports_syntax_errors() {
+        let mut game_state = scripted_state("harvest(");
+        let mut unit_scripts = UnitScripts::new(DEFAULT_INSTRUCTION_BUDGET);
+        assert_eq!(unit_scripts.step(&mut game_state).len(), 1);
+    }
+
+    #[test]
+    fn load_program_replaces_script() {
+        let mut game_state = scripted_state("harvest(2, \"iron\")");
+        let mut game_input = GameInput::default();
+        game_input.user_commands.push(UserCommand::LoadProgram(
+            Entity(3),
+            vec![Command::Harvest(Entity(2), Resource::Iron)],
+        ));
+        game_state = game_update(game_state, 0.1, &game_input).state;
+
+        assert_eq!(game_state.memory.get(&Entity(3)).unwrap().script, None);
+    }
+}
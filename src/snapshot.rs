@@ -0,0 +1,497 @@
+//! Versioned save files for a GameState.
+//!
+//! Snapshots are written as RON so two of them can be diffed by hand:
+//!
+//! ```text
+//! (
+//!     version: 12,
+//!     tick: 120,
+//!     next_entity: (4),
+//!     entities: [(1), (2), (3)],
+//!     free_entities: [],
+//!     hive_entity: Some((1)),
+//!     positions: [((1), (x: 0, y: 0, offset: (x: 0, y: 0))), ...],
+//!     collision: [...],
+//!     energy_levels: [...],
+//!     iron_mines: [...],
+//!     memory: [((3), (program_counter: 0, commands: [Harvest((2), Iron), ...], ...))],
+//!     solid_containers: [...],
+//!     world_bounds: (width: 20, height: 20, layout: Square),
+//!     tiles: [((5, 10), (walkable: true, terrain_cost: 1, deposit: Some(Iron))), ...],
+//!     energy: (move_p: 1, move_d: 1, harvest: 2, deposit: 1, recharge: 5),
+//! )
+//! ```
+//!
+//! Every component list is a list of (entity, component) pairs sorted by entity
+//! and the entity list itself is sorted, so saving the same state twice produces
+//! the same file. `version` is bumped whenever the layout changes, files with any
+//! other version are rejected instead of guessed at.
+//!
+//! Unit scripts are saved as source only, a loaded unit starts its script over.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity_manager::{Entity, EntityManager};
+use crate::game_state::{
+    Collision, Command, ComponentManager, Condition, EnergyConfig, EnergyLevel, GameState, Memory,
+    MineableNode, Position, SolidContainer, Tile, TileMap, WorldBounds, MAX_TERRAIN_COST,
+    MAX_WORLD_TILES,
+};
+
+/// current snapshot layout version.
+pub const SNAPSHOT_VERSION: u32 = 12;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// file isn't valid RON or doesn't match the snapshot layout.
+    Format(String),
+    /// file was written by a different snapshot layout.
+    Version {
+        found: u32,
+        expected: u32,
+    },
+    /// a component, the hive or a program refers to an entity that doesn't exist.
+    UnknownEntity {
+        context: String,
+        entity: Entity,
+    },
+    /// the same entity shows up twice in a single list.
+    DuplicateEntity {
+        context: String,
+        entity: Entity,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot io error: {}", e),
+            SnapshotError::Format(e) => write!(f, "malformed snapshot: {}", e),
+            SnapshotError::Version { found, expected } => write!(
+                f,
+                "snapshot version {} is not supported (expected {})",
+                found, expected
+            ),
+            SnapshotError::UnknownEntity { context, entity } => {
+                write!(f, "{} refers to unknown entity {}", context, entity.0)
+            }
+            SnapshotError::DuplicateEntity { context, entity } => {
+                write!(f, "{} lists entity {} more than once", context, entity.0)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+/// only the version, read first so a file from another layout reports a
+/// version mismatch rather than a confusing parse error.
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    tick: u32,
+    next_entity: Entity,
+    entities: Vec<Entity>,
+    // destroyed entities whose indexes are reused, in EntityManager order.
+    free_entities: Vec<Entity>,
+    hive_entity: Option<Entity>,
+    positions: Vec<(Entity, Position)>,
+    collision: Vec<(Entity, Collision)>,
+    energy_levels: Vec<(Entity, EnergyLevel)>,
+    iron_mines: Vec<(Entity, MineableNode)>,
+    memory: Vec<(Entity, Memory)>,
+    solid_containers: Vec<(Entity, SolidContainer)>,
+    world_bounds: WorldBounds,
+    // only tiles that differ from Tile::default.
+    tiles: Vec<((u32, u32), Tile)>,
+    energy: EnergyConfig,
+}
+
+fn sorted_components<T: Clone + Default>(manager: &ComponentManager<T>) -> Vec<(Entity, T)> {
+    let mut res: Vec<(Entity, T)> = manager.iter().map(|(e, c)| (*e, c.clone())).collect();
+    res.sort_by_key(|(e, _)| *e);
+    return res;
+}
+
+fn restore_components<T: Default>(
+    name: &str,
+    entities: &HashSet<Entity>,
+    saved: Vec<(Entity, T)>,
+) -> Result<ComponentManager<T>, SnapshotError> {
+    let mut manager = ComponentManager::<T>::new();
+    for (entity, component) in saved {
+        if !entities.contains(&entity) {
+            return Err(SnapshotError::UnknownEntity {
+                context: name.to_string(),
+                entity: entity,
+            });
+        }
+        if manager.contains(&entity) {
+            return Err(SnapshotError::DuplicateEntity {
+                context: name.to_string(),
+                entity: entity,
+            });
+        }
+        manager.create_with(&entity, component);
+    }
+    return Ok(manager);
+}
+
+/// entities a command points at, they must exist for the program to make sense.
+fn command_target(command: &Command) -> Option<&Entity> {
+    match command {
+        Command::Harvest(e, _) => Some(e),
+        Command::Deposit(e) => Some(e),
+        Command::JumpIf(Condition::MineEmpty(e), _) => Some(e),
+        _ => None,
+    }
+}
+
+/// serializes the game state into the snapshot format.
+pub fn to_string(game_state: &GameState) -> Result<String, SnapshotError> {
+    let mut entities: Vec<Entity> = game_state.entity_manager.entities.iter().cloned().collect();
+    entities.sort();
+
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        tick: game_state.tick,
+        next_entity: game_state.entity_manager.next_id(),
+        entities: entities,
+        free_entities: game_state.entity_manager.free_entities().to_vec(),
+        hive_entity: game_state.hive_entity,
+        positions: sorted_components(&game_state.positions),
+        collision: sorted_components(&game_state.collision),
+        energy_levels: sorted_components(&game_state.energy_levels),
+        iron_mines: sorted_components(&game_state.iron_mines),
+        memory: sorted_components(&game_state.memory),
+        solid_containers: sorted_components(&game_state.solid_containers),
+        world_bounds: game_state.get_world_bounds().clone(),
+        tiles: game_state.tile_map.changed_tiles(),
+        energy: game_state.energy.clone(),
+    };
+
+    ron::ser::to_string_pretty(&snapshot, ron::ser::PrettyConfig::default())
+        .map_err(|e| SnapshotError::Format(e.to_string()))
+}
+
+/// parses and validates a snapshot produced by to_string.
+pub fn from_str(contents: &str) -> Result<GameState, SnapshotError> {
+    let header: SnapshotHeader =
+        ron::de::from_str(contents).map_err(|e| SnapshotError::Format(e.to_string()))?;
+    if header.version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version {
+            found: header.version,
+            expected: SNAPSHOT_VERSION,
+        });
+    }
+
+    let snapshot: Snapshot =
+        ron::de::from_str(contents).map_err(|e| SnapshotError::Format(e.to_string()))?;
+
+    // every index is either alive or free, never both.
+    let mut indexes = HashSet::new();
+    let mut entities = HashSet::new();
+    let all = snapshot
+        .entities
+        .iter()
+        .map(|e| ("entities", e))
+        .chain(snapshot.free_entities.iter().map(|e| ("free_entities", e)));
+    for (context, entity) in all {
+        if entity.index() >= snapshot.next_entity.index() {
+            return Err(SnapshotError::Format(format!(
+                "entity {} is not below next_entity {}",
+                entity.0, snapshot.next_entity.0
+            )));
+        }
+        if !indexes.insert(entity.index()) {
+            return Err(SnapshotError::DuplicateEntity {
+                context: context.to_string(),
+                entity: *entity,
+            });
+        }
+        if context == "entities" {
+            entities.insert(*entity);
+        }
+    }
+
+    if let Some(hive) = snapshot.hive_entity {
+        if !entities.contains(&hive) {
+            return Err(SnapshotError::UnknownEntity {
+                context: "hive_entity".to_string(),
+                entity: hive,
+            });
+        }
+    }
+
+    for (owner, memory) in snapshot.memory.iter() {
+        // an empty program sits at 0, anything else has to point at a command.
+        let pc = memory.program_counter as usize;
+        if pc > 0 && pc >= memory.commands.len() {
+            return Err(SnapshotError::Format(format!(
+                "program counter {} of entity {} is past its {} commands",
+                pc,
+                owner.0,
+                memory.commands.len()
+            )));
+        }
+        for target in memory.commands.iter().filter_map(command_target) {
+            // programs may still point at destroyed entities, but not at ones never created.
+            if !entities.contains(target) && target.index() >= snapshot.next_entity.index() {
+                return Err(SnapshotError::UnknownEntity {
+                    context: format!("program of entity {}", owner.0),
+                    entity: *target,
+                });
+            }
+        }
+    }
+
+    let mut game_state = GameState::new();
+    game_state.positions = restore_components("positions", &entities, snapshot.positions)?;
+    game_state.collision = restore_components("collision", &entities, snapshot.collision)?;
+    game_state.energy_levels =
+        restore_components("energy_levels", &entities, snapshot.energy_levels)?;
+    game_state.iron_mines = restore_components("iron_mines", &entities, snapshot.iron_mines)?;
+    game_state.memory = restore_components("memory", &entities, snapshot.memory)?;
+    game_state.solid_containers =
+        restore_components("solid_containers", &entities, snapshot.solid_containers)?;
+    game_state.hive_entity = snapshot.hive_entity;
+    let bounds = &snapshot.world_bounds;
+    if bounds.tile_count().map_or(true, |n| n > MAX_WORLD_TILES) {
+        return Err(SnapshotError::Format(format!(
+            "{}x{} world is more than {} tiles",
+            bounds.width, bounds.height, MAX_WORLD_TILES
+        )));
+    }
+    game_state.tile_map = TileMap::new(snapshot.world_bounds);
+    for ((x, y), tile) in snapshot.tiles {
+        if tile.terrain_cost == 0 || tile.terrain_cost > MAX_TERRAIN_COST {
+            return Err(SnapshotError::Format(format!(
+                "terrain_cost {} of tile {}, {} is not between 1 and {}",
+                tile.terrain_cost, x, y, MAX_TERRAIN_COST
+            )));
+        }
+        match game_state.tile_map.get_mut(x, y) {
+            Some(t) => *t = tile,
+            None => {
+                return Err(SnapshotError::Format(format!(
+                    "tile {}, {} is outside the world",
+                    x, y
+                )))
+            }
+        }
+    }
+    game_state.tick = snapshot.tick;
+    game_state.energy = snapshot.energy;
+    game_state.entity_manager =
+        EntityManager::from_parts(entities, snapshot.next_entity, snapshot.free_entities);
+
+    return Ok(game_state);
+}
+
+pub fn save(game_state: &GameState, path: &Path) -> Result<(), SnapshotError> {
+    fs::write(path, to_string(game_state)?)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<GameState, SnapshotError> {
+    from_str(&fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::{game_load, game_update, GameInput, UserCommand};
+    use crate::resource::Resource;
+
+    fn running_state() -> GameState {
+        let mut game_state = game_load();
+        let mut game_input = GameInput::default();
+        game_input.create_unit = true;
+        game_state = game_update(game_state, 0.1, &game_input).state;
+
+        let unit = **game_state.get_programable_units().first().unwrap();
+        let mut game_input = GameInput::default();
+        game_input.user_commands.push(UserCommand::LoadProgram(
+            unit,
+            vec![
+                Command::MoveD(Position::new(10, 5)),
+                Command::Harvest(Entity(2), Resource::Iron),
+                Command::Deposit(Entity(1)),
+            ],
+        ));
+        game_update(game_state, 0.1, &game_input).state
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut game_state = running_state();
+        game_state.tile_map.get_mut(3, 4).unwrap().walkable = false;
+        let saved = to_string(&game_state).unwrap();
+        let loaded = from_str(&saved).unwrap();
+
+        assert_eq!(to_string(&loaded).unwrap(), saved);
+        assert_eq!(
+            loaded.entity_manager.count(),
+            game_state.entity_manager.count()
+        );
+        assert_eq!(
+            loaded.entity_manager.next_id(),
+            game_state.entity_manager.next_id()
+        );
+        assert_eq!(loaded.hive_entity, game_state.hive_entity);
+        assert_eq!(loaded.get_tick(), 2);
+        assert_eq!(loaded.tile_map, game_state.tile_map);
+    }
+
+    #[test]
+    fn snapshot_keeps_destroyed_entities() {
+        let mut game_state = running_state();
+        let mine = Entity(2);
+        assert!(game_state.destroy_entity(&mine));
+
+        // the unit's program still points at the mine.
+        let mut loaded = from_str(&to_string(&game_state).unwrap()).unwrap();
+        assert!(!loaded.is_alive(&mine));
+        // the index is reused with the same generation as in the saved state.
+        let reused = loaded.entity_manager.create();
+        assert_eq!(reused, game_state.entity_manager.create());
+        assert_eq!(reused, Entity::from_parts(2, 1));
+    }
+
+    #[test]
+    fn snapshot_rejects_reused_index() {
+        let mut game_state = running_state();
+        game_state.destroy_entity(&Entity(2));
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        snapshot.entities.push(Entity::from_parts(2, 1));
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::DuplicateEntity { .. }) => (),
+            _ => panic!("expected a duplicate entity error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_other_version() {
+        let saved = to_string(&running_state()).unwrap();
+        let bumped = saved.replacen(
+            &format!("version: {}", SNAPSHOT_VERSION),
+            &format!("version: {}", SNAPSHOT_VERSION + 1),
+            1,
+        );
+
+        match from_str(&bumped) {
+            Err(SnapshotError::Version { found, expected }) => {
+                assert_eq!(found, SNAPSHOT_VERSION + 1);
+                assert_eq!(expected, SNAPSHOT_VERSION);
+            }
+            _ => panic!("expected a version error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_unknown_entity() {
+        let game_state = running_state();
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        snapshot.positions.push((Entity(99), Position::new(1, 1)));
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::UnknownEntity { entity, .. }) => assert_eq!(entity, Entity(99)),
+            _ => panic!("expected an unknown entity error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_unknown_program_target() {
+        let game_state = running_state();
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        snapshot.memory[0]
+            .1
+            .commands
+            .push(Command::Harvest(Entity(42), Resource::Iron));
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::UnknownEntity { entity, .. }) => assert_eq!(entity, Entity(42)),
+            _ => panic!("expected an unknown entity error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_bad_program_counter() {
+        let game_state = running_state();
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        let memory = &mut snapshot.memory[0].1;
+        assert!(!memory.commands.is_empty());
+        memory.program_counter = memory.commands.len() as u32;
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::Format(_)) => (),
+            _ => panic!("expected a format error"),
+        }
+
+        // an empty program at 0 is how a fresh unit starts.
+        let memory = &mut snapshot.memory[0].1;
+        memory.commands.clear();
+        memory.program_counter = 0;
+        assert!(from_str(&ron::ser::to_string(&snapshot).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn snapshot_rejects_bad_terrain_cost() {
+        let game_state = running_state();
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        let tile = Tile {
+            terrain_cost: MAX_TERRAIN_COST + 1,
+            ..Tile::default()
+        };
+        snapshot.tiles.push(((1, 1), tile));
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::Format(_)) => (),
+            _ => panic!("expected a format error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_huge_world() {
+        let game_state = running_state();
+        let mut snapshot: Snapshot = ron::de::from_str(&to_string(&game_state).unwrap()).unwrap();
+        snapshot.world_bounds.width = u32::MAX;
+        snapshot.world_bounds.height = u32::MAX;
+        let saved = ron::ser::to_string(&snapshot).unwrap();
+
+        match from_str(&saved) {
+            Err(SnapshotError::Format(_)) => (),
+            _ => panic!("expected a format error"),
+        }
+    }
+
+    #[test]
+    fn snapshot_rejects_garbage() {
+        match from_str("not a snapshot") {
+            Err(SnapshotError::Format(_)) => (),
+            _ => panic!("expected a format error"),
+        }
+    }
+}
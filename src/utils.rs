@@ -13,13 +13,41 @@ pub fn manhat_distance(x1: u32, y1: u32, x2: u32, y2: u32) -> u32 {
     x_dist.abs() as u32 + y_dist.abs() as u32
 }
 
-// whats that dudes name?
-pub fn uclid_distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
-    let x_dist = (x2 - x1).powf(2.0);
-    let y_dist = (y2 - y1).powf(2.0);
-    return (x_dist + y_dist).sqrt();
+/// axial (q, r) hex coordinates of tile x, y where odd rows are pushed half a tile right.
+pub fn offset_to_axial(x: u32, y: u32) -> (i32, i32) {
+    let q = x as i32 - (y as i32 - (y as i32 & 1)) / 2;
+    (q, y as i32)
 }
 
+/// inverse of offset_to_axial, None for tiles left of or above 0, 0.
+pub fn axial_to_offset(q: i32, r: i32) -> Option<(u32, u32)> {
+    let x = q + (r - (r & 1)) / 2;
+    if x < 0 || r < 0 {
+        return None;
+    }
+    Some((x as u32, r as u32))
+}
+
+/// number of hex steps between two tiles, the hex version of manhat_distance.
+pub fn hex_distance(x1: u32, y1: u32, x2: u32, y2: u32) -> u32 {
+    let (q1, r1) = offset_to_axial(x1, y1);
+    let (q2, r2) = offset_to_axial(x2, y2);
+    let q_dist = q1 - q2;
+    let r_dist = r1 - r2;
+    ((q_dist.abs() + r_dist.abs() + (q_dist + r_dist).abs()) / 2) as u32
+}
+
+/// the up to 6 tiles sharing an edge with x, y on a hex layout.
+/// tiles left of or above 0, 0 are left out, the far edge of the world isn't checked.
+pub fn hex_neighbours(x: u32, y: u32) -> Vec<(u32, u32)> {
+    let (q, r) = offset_to_axial(x, y);
+    [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)]
+        .iter()
+        .filter_map(|(dq, dr)| axial_to_offset(q + dq, r + dr))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Path {
     // should use position?
     pub path_points: Vec<(u32, u32)>,
@@ -54,7 +82,7 @@ pub fn generate_path_step(start_pos: (f32, f32), end_pos: (f32, f32), delta: f32
         } else {
             // movement is restricted to 1 tile at a time.
             // thus no diagional movement on this non best-agon grided layout
-            // todo: change grid layout to best-agons
+            // hex layouts path through pathfinding with Connectivity::Hex.
             if current_pos.1 < end_pos.1 {
                 next_y = current_pos.1 + delta;
             } else if current_pos.1 > end_pos.1 {
@@ -70,6 +98,7 @@ pub fn generate_path_step(start_pos: (f32, f32), end_pos: (f32, f32), delta: f32
 }
 
 /// naive direct path handling, no detection of things in the way in the slight est.
+/// see pathfinding for paths that go around obstacles.
 pub fn generate_path(start_pos: (u32, u32), end_pos: (u32, u32)) -> Path {
     let mut r_path = Path::new();
 
@@ -84,7 +113,7 @@ pub fn generate_path(start_pos: (u32, u32), end_pos: (u32, u32)) -> Path {
         } else {
             // movement is restricted to 1 tile at a time.
             // thus no diagional movement on this non best-agon grided layout
-            // todo: change grid layout to best-agons
+            // hex layouts path through pathfinding with Connectivity::Hex.
             if current_pos.1 < end_pos.1 {
                 next_y = current_pos.1 + 1;
             } else if current_pos.1 > end_pos.1 {
@@ -165,4 +194,38 @@ mod tests {
     fn manhat_dist() {
         assert_eq!(2, manhat_distance(0, 0, 1, 1));
     }
+
+    #[test]
+    fn hex_dist() {
+        assert_eq!(hex_distance(2, 2, 2, 2), 0);
+        for (x, y) in hex_neighbours(2, 2).iter() {
+            assert_eq!(hex_distance(2, 2, *x, *y), 1);
+        }
+        for (x, y) in hex_neighbours(2, 3).iter() {
+            assert_eq!(hex_distance(2, 3, *x, *y), 1);
+        }
+        // odd rows are pushed right, so 1, 1 isn't next to 0, 0.
+        assert_eq!(hex_distance(0, 0, 0, 1), 1);
+        assert_eq!(hex_distance(0, 0, 1, 1), 2);
+        assert_eq!(hex_distance(1, 0, 0, 1), 1);
+        assert_eq!(hex_distance(0, 0, 0, 2), 2);
+        assert_eq!(hex_distance(0, 0, 3, 0), 3);
+        assert_eq!(hex_distance(0, 0, 3, 4), 5);
+    }
+
+    #[test]
+    fn hex_neighbour_tiles() {
+        assert_eq!(hex_neighbours(2, 2).len(), 6);
+        assert_eq!(hex_neighbours(0, 0), vec![(1, 0), (0, 1)]);
+        let mut odd_row = hex_neighbours(1, 1);
+        odd_row.sort();
+        assert_eq!(
+            odd_row,
+            vec![(0, 1), (1, 0), (1, 2), (2, 0), (2, 1), (2, 2)]
+        );
+        for (x, y) in [(0, 0), (3, 1), (5, 6)].iter() {
+            let (q, r) = offset_to_axial(*x, *y);
+            assert_eq!(axial_to_offset(q, r), Some((*x, *y)));
+        }
+    }
 }
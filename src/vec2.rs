@@ -0,0 +1,214 @@
+#![allow(dead_code)]
+//! 2d vector math shared by the minions and asteroids simulations.
+//!
+//! Vec2 is a plain f64 pair, used both for points and for directions.
+//! Angles are in radians, 0 points along +x and PI / 2 along +y.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x: x, y: y }
+    }
+
+    /// unit vector pointing at angle.
+    pub fn from_angle(angle: f64) -> Self {
+        Self::new(angle.cos(), angle.sin())
+    }
+
+    /// angle of the vector in (-PI, PI], 0 for the zero vector.
+    pub fn angle(&self) -> f64 {
+        self.y.atan2(self.x)
+    }
+
+    pub fn dot(&self, other: &Self) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn distance(&self, other: &Self) -> f64 {
+        (*self - *other).length()
+    }
+
+    /// same direction with length 1, the zero vector stays zero.
+    pub fn normalize(&self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            return Self::ZERO;
+        }
+        *self * (1.0 / length)
+    }
+
+    /// turned counter clockwise (towards +y) by angle.
+    pub fn rotate(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// self at t = 0, other at t = 1, t isn't clamped.
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
+
+    /// moves towards target by at most max_step without overshooting it.
+    pub fn move_towards(&self, target: &Self, max_step: f64) -> Self {
+        let delta = *target - *self;
+        let length = delta.length();
+        if length <= max_step {
+            return *target;
+        }
+        *self + delta * (max_step / length)
+    }
+
+    /// tile the point is in, rounded towards negative infinity.
+    pub fn tile(&self) -> (i64, i64) {
+        (self.x.floor() as i64, self.y.floor() as i64)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Self;
+    fn mul(self, factor: f64) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
+
+    fn close(a: &Vec2, b: &Vec2) -> bool {
+        (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Vec2::new(1.0, 2.0);
+        let b = Vec2::new(3.0, -1.0);
+        assert_eq!(a + b, Vec2::new(4.0, 1.0));
+        assert_eq!(a - b, Vec2::new(-2.0, 3.0));
+        assert_eq!(a * 2.0, Vec2::new(2.0, 4.0));
+        assert_eq!(-a, Vec2::new(-1.0, -2.0));
+        assert_eq!(a.dot(&b), 1.0);
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+    }
+
+    #[test]
+    fn length_and_normalize() {
+        let v = Vec2::new(3.0, 4.0);
+        assert_eq!(v.length(), 5.0);
+        assert_eq!(v.distance(&Vec2::ZERO), 5.0);
+        assert!(close(&v.normalize(), &Vec2::new(0.6, 0.8)));
+        assert_eq!(Vec2::ZERO.normalize(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn angles_and_rotation() {
+        assert!(close(&Vec2::from_angle(0.0), &Vec2::new(1.0, 0.0)));
+        assert!(close(&Vec2::from_angle(PI * 0.5), &Vec2::new(0.0, 1.0)));
+        assert!(close(
+            &Vec2::new(1.0, 0.0).rotate(PI * 0.5),
+            &Vec2::new(0.0, 1.0)
+        ));
+        assert!(close(
+            &Vec2::new(2.0, 1.0).rotate(PI),
+            &Vec2::new(-2.0, -1.0)
+        ));
+        assert_eq!(Vec2::new(0.0, 2.0).angle(), PI * 0.5);
+        assert_eq!(Vec2::ZERO.angle(), 0.0);
+    }
+
+    #[test]
+    fn lerp_and_move_towards() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(4.0, -2.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Vec2::new(2.0, -1.0));
+
+        let target = Vec2::new(3.0, 4.0);
+        assert!(close(&a.move_towards(&target, 1.0), &Vec2::new(0.6, 0.8)));
+        // doesn't overshoot.
+        assert_eq!(a.move_towards(&target, 10.0), target);
+    }
+
+    #[test]
+    fn tiles() {
+        assert_eq!(Vec2::new(1.5, 0.0).tile(), (1, 0));
+        assert_eq!(Vec2::new(-0.5, -1.0).tile(), (-1, -1));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_rotate_keeps_length(
+            x in -1e3f64..1e3, y in -1e3f64..1e3, angle in -10f64..10.0,
+        ) {
+            let v = Vec2::new(x, y);
+            let rotated = v.rotate(angle);
+            prop_assert!((rotated.length() - v.length()).abs() < 1e-9);
+            prop_assert!(close(&rotated.rotate(-angle), &v));
+        }
+
+        #[test]
+        fn prop_normalize_is_unit(x in -1e3f64..1e3, y in -1e3f64..1e3) {
+            let v = Vec2::new(x, y);
+            prop_assume!(v.length() > 1e-6);
+            let n = v.normalize();
+            prop_assert!((n.length() - 1.0).abs() < 1e-9);
+            prop_assert!(close(&(n * v.length()), &v));
+            prop_assert!(close(&Vec2::from_angle(v.angle()), &n));
+        }
+    }
+}